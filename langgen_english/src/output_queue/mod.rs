@@ -6,6 +6,7 @@ use crate::traits::EntityAdapter;
 use crate::traits::QueueAdapter;
 use crate::FragmentEntry;
 pub use output_builder::*;
+pub use output_helper::make_plural;
 use output_helper::*;
 use std::marker::PhantomData;
 
@@ -231,7 +232,9 @@ where
         if entity_adapter.is_me(obj) {
             self.add_string("you");
         } else if entity_adapter.can_see(self.who, obj) {
-            if !is_prop && is_singular(entity_adapter.gender(obj)) {
+            // Counted names start with how many there are instead.
+            let needs_article = !is_prop && entity_adapter.count(obj) == 1;
+            if needs_article && is_singular(entity_adapter.gender(obj)) {
                 let mut should_be_an = false;
                 if let Some(c) = name.chars().next() {
                     if is_vowel(c) {
@@ -243,7 +246,7 @@ where
                 } else {
                     self.add_string("a");
                 }
-            } else if !is_prop {
+            } else if needs_article {
                 self.add_string("some");
             }
             self.add_string(name);
@@ -275,6 +278,28 @@ where
         }
     }
 
+    fn add_my_word<A: EntityAdapter<Entity>>(
+        &mut self,
+        entity_adapter: &A,
+        who: Entity,
+        obj: Entity,
+        name: &str,
+        is_proper: bool,
+    ) {
+        if !entity_adapter.can_see(self.who, obj) {
+            self.add_a_word(entity_adapter, obj, name, is_proper);
+            return;
+        }
+        if entity_adapter.is_me(who) {
+            self.add_string("your");
+        } else if entity_adapter.can_see(self.who, who) {
+            self.add_string(possessive_pronoun(gender_of(entity_adapter, who)));
+        } else {
+            self.add_string("their");
+        }
+        self.add_string(name);
+    }
+
     fn sing_plur<A: EntityAdapter<Entity>>(
         &mut self,
         entity_adapter: &A,
@@ -282,7 +307,7 @@ where
         singular: &'static str,
         plural: &'static str,
     ) {
-        let mut g = entity_adapter.gender(who);
+        let mut g = gender_of(entity_adapter, who);
         if entity_adapter.is_me(who) {
             g = crate::Gender::Plural;
         } else if !entity_adapter.can_see(self.who, who) {
@@ -304,7 +329,7 @@ where
         self.push_str(verb);
         self.supress_capitalize = true;
         self.add_space = false;
-        if is_singular(entity_adapter.gender(who)) && !entity_adapter.is_me(who) {
+        if is_singular(gender_of(entity_adapter, who)) && !entity_adapter.is_me(who) {
             add_verb_end_s(&mut self.output_string);
         }
         self.add_string("");
@@ -318,7 +343,7 @@ where
                 A(obj) => {
                     // TODO; Fix add_a_word to take function.
                     let mut s = String::new();
-                    entity_adapter.append_short_name(obj, &mut s);
+                    append_name(entity_adapter, obj, false, &mut s);
                     self.add_a_word(
                         entity_adapter,
                         obj,
@@ -328,12 +353,12 @@ where
                 }
                 A_(obj) => {
                     let mut s = String::new();
-                    entity_adapter.append_long_name(obj, &mut s);
+                    append_name(entity_adapter, obj, true, &mut s);
                     self.add_a_word(entity_adapter, obj, &s, entity_adapter.has_long_proper(obj));
                 }
                 The(obj) => {
                     let mut s = String::new();
-                    entity_adapter.append_short_name(obj, &mut s);
+                    append_name(entity_adapter, obj, false, &mut s);
                     self.add_the_word(
                         entity_adapter,
                         obj,
@@ -343,7 +368,7 @@ where
                 }
                 The_(obj) => {
                     let mut s = String::new();
-                    entity_adapter.append_long_name(obj, &mut s);
+                    append_name(entity_adapter, obj, true, &mut s);
                     self.add_the_word(entity_adapter, obj, &s, entity_adapter.has_long_proper(obj));
                 }
                 Thes(obj) => {
@@ -425,13 +450,27 @@ where
                     /* TODO */
                     unimplemented!();
                 }
-                My(_who, _obj) => {
-                    /* TODO */
-                    unimplemented!();
+                My(who, obj) => {
+                    let mut s = String::new();
+                    append_name(entity_adapter, obj, false, &mut s);
+                    self.add_my_word(
+                        entity_adapter,
+                        who,
+                        obj,
+                        &s,
+                        entity_adapter.has_short_proper(obj),
+                    );
                 }
-                My_(_who, _obj) => {
-                    /* TODO */
-                    unimplemented!();
+                My_(who, obj) => {
+                    let mut s = String::new();
+                    append_name(entity_adapter, obj, true, &mut s);
+                    self.add_my_word(
+                        entity_adapter,
+                        who,
+                        obj,
+                        &s,
+                        entity_adapter.has_long_proper(obj),
+                    );
                 }
                 Word(_obj) => {
                     /* TODO */
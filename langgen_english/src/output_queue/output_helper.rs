@@ -1,3 +1,4 @@
+use crate::EntityAdapter;
 use crate::Gender;
 
 pub(crate) fn last_char(s: &str) -> Option<char> {
@@ -28,10 +29,77 @@ pub(crate) fn uppercase_first_char(s: &str, to: &mut String) {
     }
 }
 
+/// obj's gender, plural when there are more than one of it.
+pub(crate) fn gender_of<Entity: Copy, A: EntityAdapter<Entity>>(
+    entity_adapter: &A,
+    obj: Entity,
+) -> Gender {
+    if entity_adapter.count(obj) > 1 {
+        Gender::Plural
+    } else {
+        entity_adapter.gender(obj)
+    }
+}
+
+/// Appends obj's short or long name, after how many there are if more than one.
+pub(crate) fn append_name<Entity: Copy, A: EntityAdapter<Entity>>(
+    entity_adapter: &A,
+    obj: Entity,
+    long: bool,
+    s: &mut String,
+) {
+    let count = entity_adapter.count(obj);
+    if count > 1 {
+        s.push_str(&count.to_string());
+        s.push(' ');
+        if long {
+            entity_adapter.append_long_plural_name(obj, s);
+        } else {
+            entity_adapter.append_short_plural_name(obj, s);
+        }
+    } else if long {
+        entity_adapter.append_long_name(obj, s);
+    } else {
+        entity_adapter.append_short_name(obj, s);
+    }
+}
+
 pub(crate) fn is_singular(gender: Gender) -> bool {
     !matches!(gender, Gender::Plural | Gender::Uncountable)
 }
 
+pub(crate) fn possessive_pronoun(gender: Gender) -> &'static str {
+    match gender {
+        Gender::Male => "his",
+        Gender::Female => "her",
+        Gender::Neuter | Gender::Uncountable => "its",
+        Gender::Plural => "their",
+    }
+}
+
+/// Turns the noun at the end of `name` into its plural, like "apple" to
+/// "apples", "red berry" to "red berries" or "knife" to "knives".
+///
+/// Apart from the f that turns into a v, nouns take the same ending as
+/// verbs in the third person.
+pub fn make_plural(name: &mut String) {
+    let lower = name.to_lowercase();
+    let f_len = if lower.ends_with("fe") {
+        2
+    } else if lower.ends_with("lf") || lower.ends_with("rf") {
+        1
+    } else {
+        0
+    };
+    if f_len > 0 {
+        let uc = name.ends_with(|c: char| c.is_uppercase());
+        name.truncate(name.len() - f_len);
+        name.push_str(if uc { "VES" } else { "ves" });
+        return;
+    }
+    add_verb_end_s(name);
+}
+
 pub(crate) fn add_verb_end_s(str: &mut String) {
     let mut add: &str = "";
     let mut uc = false;
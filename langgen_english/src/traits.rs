@@ -20,6 +20,12 @@ where
     /// Is obj a thing?
     fn is_thing(&self, obj: Entity) -> bool;
 
+    /// How many of obj there are, 1 unless it is a stack of things.
+    /// When more than 1, obj is plural and named by its plural name.
+    fn count(&self, _obj: Entity) -> u32 {
+        1
+    }
+
     /// Does obj have a proper name (ie Thomas)?
     fn has_short_proper(&self, obj: Entity) -> bool;
 
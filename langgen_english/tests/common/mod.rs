@@ -7,6 +7,7 @@ pub struct DebugEntityAdapter {
     pub mock_is_me: bool,
     pub mock_gender: Gender,
     pub mock_is_thing: bool,
+    pub mock_count: u32,
     pub mock_has_short_proper: bool,
     pub mock_short_name: &'static str,
     pub mock_has_long_proper: bool,
@@ -26,6 +27,7 @@ impl DebugEntityAdapter {
             mock_is_me: false,
             mock_gender: Gender::Neuter,
             mock_is_thing: false,
+            mock_count: 1,
             mock_has_short_proper: true,
             mock_short_name: "Kim",
             mock_has_long_proper: false,
@@ -60,6 +62,11 @@ impl EntityAdapter<i32> for DebugEntityAdapter {
         self.mock_is_thing
     }
 
+    fn count(&self, obj: i32) -> u32 {
+        self.last_obj.set(obj);
+        self.mock_count
+    }
+
     fn has_short_proper(&self, obj: i32) -> bool {
         self.last_obj.set(obj);
         self.mock_has_short_proper
@@ -53,3 +53,74 @@ fn output_advanced_sentance() {
         dea.buffer
     );
 }
+
+#[test]
+fn output_my_sword() {
+    let mut oq = DebOutputQueue::new(Mutex::new(VecDeque::new()), 16);
+    let mut dea = DebugEntityAdapter::new();
+    dea.mock_gender = Gender::Female;
+    dea.mock_short_name = "sword";
+    dea.mock_has_short_proper = false;
+
+    oq.s("Lamotte").v(8, "swing").my(8, 9);
+    oq.process_queue(&mut dea);
+    assert_eq!("Lamotte swings her sword.\n", dea.buffer);
+
+    dea.buffer.clear();
+    dea.mock_is_me = true;
+    oq.the(16).v(16, "swing").my(16, 9);
+    oq.process_queue(&mut dea);
+    assert_eq!("You swing your sword.\n", dea.buffer);
+
+    dea.buffer.clear();
+    dea.mock_is_me = false;
+    dea.mock_can_see = false;
+    oq.s("Lamotte").v(8, "swing").my(8, 9);
+    oq.process_queue(&mut dea);
+    assert_eq!("Lamotte swings something.\n", dea.buffer);
+}
+
+#[test]
+fn output_apples() {
+    let mut oq = DebOutputQueue::new(Mutex::new(VecDeque::new()), 16);
+    let mut dea = DebugEntityAdapter::new();
+    dea.mock_short_name = "apple";
+    dea.mock_short_plural_name = "apples";
+    dea.mock_has_short_proper = false;
+    dea.mock_count = 3;
+
+    oq.s("Lamotte picks up").a(9);
+    oq.process_queue(&mut dea);
+    assert_eq!("Lamotte picks up 3 apples.\n", dea.buffer);
+
+    dea.buffer.clear();
+    oq.the(9).is(9).s("red");
+    oq.process_queue(&mut dea);
+    assert_eq!("The 3 apples are red.\n", dea.buffer);
+
+    dea.buffer.clear();
+    dea.mock_count = 1;
+    oq.the(9).v(9, "fall");
+    oq.process_queue(&mut dea);
+    assert_eq!("The apple falls.\n", dea.buffer);
+}
+
+#[test]
+fn plurals() {
+    for (singular, plural) in [
+        ("apple", "apples"),
+        ("red berry", "red berries"),
+        ("key", "keys"),
+        ("box", "boxes"),
+        ("torch", "torches"),
+        ("health potion", "health potions"),
+        ("knife", "knives"),
+        ("grey wolf", "grey wolves"),
+        ("DWARF", "DWARVES"),
+        ("scarf", "scarves"),
+    ] {
+        let mut name = singular.to_string();
+        make_plural(&mut name);
+        assert_eq!(plural, name);
+    }
+}
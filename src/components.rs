@@ -1,21 +1,101 @@
 use crate::MapPosition;
-use ::bracket_lib::prelude::RGB;
+use ::bracket_lib::prelude::{RGB, RandomNumberGenerator};
 use ::legion::Entity;
 use ::legion_typeuuid::*;
 use ::serde::*;
+use ::std::collections::HashMap;
 use ::type_uuid::*;
 
+/// The monster heard something, and goes to look unless it sees the player.
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "c9aa1b74-d8a0-4239-b0c4-80e9ed70395c"]
+pub(crate) struct Alerted {
+    pub pos: MapPosition,
+}
+register_component!(Alerted);
+
+/// What launchers fire, each fires only its own kind.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AmmoKind {
+    Arrow,
+    Stone,
+}
+
+impl AmmoKind {
+    pub(crate) fn plural(self) -> &'static str {
+        match self {
+            AmmoKind::Arrow => "arrows",
+            AmmoKind::Stone => "stones",
+        }
+    }
+}
+
+/// The item is fired from a launcher of the same kind.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "c3bdf471-cec0-43ea-a55e-6f474085effb"]
+pub(crate) struct Ammunition {
+    pub kind: AmmoKind,
+}
+register_component!(Ammunition);
+
+/// The player's attributes, raised when levelling up.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "82046dab-87a8-40ef-9b40-0b1860ec70ba"]
+pub(crate) struct Attributes {
+    /// Adds to the melee attack rolls and damage.
+    pub strength: i32,
+    /// Adds to the defense.
+    pub dexterity: i32,
+    /// Adds to the hit points gained at each level.
+    pub constitution: i32,
+}
+register_component!(Attributes);
+
+impl Attributes {
+    /// What an attribute adds to the stats, 10 is average.
+    pub(crate) fn bonus(value: i32) -> i32 {
+        (value - 10).div_euclid(2)
+    }
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            strength: 10,
+            dexterity: 10,
+            constitution: 10,
+        }
+    }
+}
+
+/// The shape of an item's area of effect, see `Map::area`.
+/// Areas of items without one are bursts.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, TypeUuid)]
+#[uuid = "4e235895-d48b-4e94-b14c-11529d248d67"]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AreaShape {
+    /// Everything within the radius of the target.
+    #[default]
+    Burst,
+    /// Widening from the user towards the target.
+    Cone,
+    /// From the user through the target.
+    Line,
+}
+register_component!(AreaShape);
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, TypeUuid)]
 #[uuid = "0ba9a288-a1a7-45b5-8964-44cbc0a8b953"]
 pub(crate) struct AreaOfEffect {
     pub radius: i32,
 }
-register_serialize!(AreaOfEffect);
+register_component!(AreaOfEffect);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "b7e17796-b15d-4498-b3b0-4eeb79af3878"]
 pub(crate) struct BlocksTile {}
-register_serialize!(BlocksTile);
+register_component!(BlocksTile);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "7b5aa67d-1cff-49f6-bdab-6f446f9d22a1"]
@@ -25,12 +105,64 @@ pub(crate) struct CombatStats {
     pub defense: i32,
     pub power: i32,
 }
-register_serialize!(CombatStats);
+register_component!(CombatStats);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "f149f629-12cd-4a04-a158-ad4fbfd221d7"]
 pub(crate) struct Consumable {}
-register_serialize!(Consumable);
+register_component!(Consumable);
+
+/// What kind of harm a melee attack does, the targets can resist some kinds.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DamageType {
+    Bludgeon,
+    Slash,
+    Pierce,
+    Fire,
+}
+
+impl DamageType {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            DamageType::Bludgeon => "bludgeon",
+            DamageType::Slash => "slash",
+            DamageType::Pierce => "pierce",
+            DamageType::Fire => "fire",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "e8191594-1be8-46b1-99e1-5bc57103a536"]
+pub(crate) struct DefenseBonus {
+    pub defense: i32,
+}
+register_component!(DefenseBonus);
+
+/// Some dice, like 2d6+1.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Dice {
+    pub count: i32,
+    pub sides: i32,
+    pub bonus: i32,
+}
+
+impl Dice {
+    pub(crate) fn roll(&self, rng: &mut RandomNumberGenerator) -> i32 {
+        rng.roll_dice(self.count, self.sides) + self.bonus
+    }
+}
+
+impl std::fmt::Display for Dice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        if self.bonus != 0 {
+            write!(f, "{:+}", self.bonus)?;
+        }
+        Ok(())
+    }
+}
 
 /// The object is removed at the given tick.
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
@@ -38,7 +170,7 @@ register_serialize!(Consumable);
 pub(crate) struct EndTick {
     pub end_tick: i64,
 }
-register_serialize!(EndTick);
+register_component!(EndTick);
 
 /// The object is removed at the given time.
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
@@ -46,7 +178,7 @@ register_serialize!(EndTick);
 pub(crate) struct EndTime {
     pub end_time_ms: i64,
 }
-register_serialize!(EndTime);
+register_component!(EndTime);
 
 /// Animated objects need energy to perform actions.
 /// The more an action cost, the more energy it drains.
@@ -59,45 +191,274 @@ register_serialize!(EndTime);
 pub(crate) struct Energy {
     pub energy: i32,
 }
-register_serialize!(Energy);
+register_component!(Energy);
+
+/// Where an item is wielded or worn, one item per slot.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EquipmentSlot {
+    Weapon,
+    Shield,
+    Head,
+    Body,
+    /// A launcher, wielded beside the melee weapon.
+    Ranged,
+}
+
+impl EquipmentSlot {
+    /// Whether what goes in the slot is wielded rather than worn.
+    pub(crate) fn is_wielded(self) -> bool {
+        matches!(self, EquipmentSlot::Weapon | EquipmentSlot::Ranged)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "2f991320-98e8-4a38-99f3-ca46a6cbd36d"]
+pub(crate) struct Equippable {
+    pub slot: EquipmentSlot,
+}
+register_component!(Equippable);
+
+/// The item is wielded or worn by the owner. The player's equipment stays in
+/// their backpack, the monsters' only has this.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "4b7a4af7-5f40-48ae-a113-9e04a6f832f6"]
+pub(crate) struct Equipped {
+    pub owner: Entity,
+    pub slot: EquipmentSlot,
+}
+register_component!(Equipped);
+
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "61346264-14f6-4581-93c4-a021638952b1"]
+pub(crate) struct Experience {
+    pub level: i32,
+    pub xp: i32,
+    /// Attribute points gained by levelling up, and not spent yet.
+    pub unspent_points: i32,
+}
+register_component!(Experience);
+
+impl Experience {
+    /// The total xp needed to reach the next level.
+    pub(crate) fn next_level_xp(&self) -> i32 {
+        25 * self.level * (self.level + 1)
+    }
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self {
+            level: 1,
+            xp: 0,
+            unspent_points: 0,
+        }
+    }
+}
+
+/// The xp awarded to whoever kills it.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "30052a0d-ae53-4cdf-a548-0a41e51fd883"]
+pub(crate) struct ExperienceValue {
+    pub xp: i32,
+}
+register_component!(ExperienceValue);
+
+/// Which pronouns are used, "he" for those without it.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, TypeUuid)]
+#[serde(rename_all = "snake_case")]
+#[uuid = "3159dd25-24cd-432e-acd2-735098a6ee97"]
+pub(crate) enum Gender {
+    Male,
+    Female,
+    Neuter,
+    Plural,
+}
+register_component!(Gender);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "9ea2eda5-8e86-48ca-a831-8044fe7f4064"]
 pub(crate) struct HealthProvider {
     pub heal_amount: i32,
 }
-register_serialize!(HealthProvider);
+register_component!(HealthProvider);
+
+/// Isn't drawn or named until it is found, ie a trap before searching.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "c13f889f-855a-43c0-8057-bfbde1894933"]
+pub(crate) struct Hidden {}
+register_component!(Hidden);
+
+/// How hungry the player is, moves on to the next state at `end_tick`.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "9a489aae-7f22-4646-a066-11e9fd41d951"]
+pub(crate) struct HungerClock {
+    pub state: HungerState,
+    /// When fainting, the next turn instead.
+    pub end_tick: i64,
+}
+register_component!(HungerClock);
+
+impl HungerClock {
+    pub(crate) fn well_fed(tick: i64) -> Self {
+        Self {
+            state: HungerState::WellFed,
+            end_tick: tick + HungerState::WellFed.duration(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Weak,
+    Fainting,
+}
+
+impl HungerState {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            HungerState::WellFed => "Well fed",
+            HungerState::Normal => "Normal",
+            HungerState::Hungry => "Hungry",
+            HungerState::Weak => "Weak",
+            HungerState::Fainting => "Fainting",
+        }
+    }
+
+    /// How many ticks it lasts, a move takes 100. Fainting lasts until eating,
+    /// this is how often it hurts.
+    pub(crate) fn duration(self) -> i64 {
+        match self {
+            HungerState::WellFed => 20_000,
+            HungerState::Normal => 60_000,
+            HungerState::Hungry => 30_000,
+            HungerState::Weak => 20_000,
+            HungerState::Fainting => 100,
+        }
+    }
+
+    /// What it adds to the melee power.
+    pub(crate) fn power_modifier(self) -> i32 {
+        match self {
+            HungerState::WellFed => 1,
+            HungerState::Normal | HungerState::Hungry => 0,
+            HungerState::Weak => -1,
+            HungerState::Fainting => -2,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Clone, TypeUuid)]
 #[uuid = "6f3fedb4-3dd9-4a2d-a2a6-51149b614254"]
 pub(crate) struct InBackpack {
     pub owner: Entity,
 }
-register_serialize!(InBackpack);
+register_component!(InBackpack);
+
+/// Using the item puts a status effect on the targets.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "686ef784-ba1f-4da3-9a6f-f5bef0053fb3"]
+pub(crate) struct InflictsStatus {
+    pub kind: StatusKind,
+    pub turns: i32,
+}
+register_component!(InflictsStatus);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "0d38045c-4cb0-46f6-aec3-92c478e4a6db"]
 pub(crate) struct InflictsDamage {
     pub damage: i32,
 }
-register_serialize!(InflictsDamage);
+register_component!(InflictsDamage);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "3fe6f537-42ab-4ea7-868b-06dd465ec123"]
 pub(crate) struct Item {}
-register_serialize!(Item);
+register_component!(Item);
 
 #[derive(Serialize, Deserialize, Debug, Clone, TypeUuid)]
 #[uuid = "e34d9ba1-6289-4c1c-95fb-0075ee34fa09"]
 pub(crate) struct ItemIndex {
     pub index: u8,
 }
-register_serialize!(ItemIndex);
+register_component!(ItemIndex);
+
+/// Who damaged it last, the player gets the xp if it dies.
+///
+/// Only whether it was the player is kept, entities aren't the same
+/// when an archived level is restored.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "f45623e5-f072-452c-b942-1affeb56dea4"]
+pub(crate) struct LastHitBy {
+    pub by_player: bool,
+}
+register_component!(LastHitBy);
+
+/// A bow or sling, which fires the ammunition up to `range` tiles away.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "6a8981ed-9e17-479c-8225-44487f8bbf83"]
+pub(crate) struct Launcher {
+    pub ammunition: AmmoKind,
+    pub range: i32,
+}
+register_component!(Launcher);
+
+/// The damage done by a weapon, or by a creature fighting without one.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "30e3728f-23f0-499f-b782-44c872b9a0a4"]
+pub(crate) struct MeleeDamage {
+    pub dice: Dice,
+    pub damage_type: DamageType,
+}
+register_component!(MeleeDamage);
+
+impl MeleeDamage {
+    /// Fists, for those without anything better.
+    pub(crate) const UNARMED: MeleeDamage = MeleeDamage {
+        dice: Dice {
+            count: 1,
+            sides: 4,
+            bonus: 0,
+        },
+        damage_type: DamageType::Bludgeon,
+    };
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "076c36db-5193-4d26-8faf-eff508d4a557"]
+pub(crate) struct MeleePowerBonus {
+    pub power: i32,
+}
+register_component!(MeleePowerBonus);
+
+/// The damage done by the item when it is thrown or fired.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "a4377fff-1b26-4e77-9b9e-9581322ff333"]
+pub(crate) struct MissileDamage {
+    pub dice: Dice,
+    pub damage_type: DamageType,
+}
+register_component!(MissileDamage);
+
+impl MissileDamage {
+    /// Anything else thrown at someone.
+    pub(crate) const IMPROVISED: MissileDamage = MissileDamage {
+        dice: Dice {
+            count: 1,
+            sides: 2,
+            bonus: 0,
+        },
+        damage_type: DamageType::Bludgeon,
+    };
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "974bf33c-2dd4-4317-9747-680e4ecefb54"]
 pub(crate) struct Monster {}
-register_serialize!(Monster);
+register_component!(Monster);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "d866e77f-91de-4917-a65d-c16d8b858543"]
@@ -105,17 +466,61 @@ pub(crate) struct Name {
     pub name: String,
     pub proper_name: bool,
 }
-register_serialize!(Name);
+register_component!(Name);
+
+impl Name {
+    pub(crate) fn plural(&self) -> String {
+        let mut plural = self.name.clone();
+        langgen_english::make_plural(&mut plural);
+        plural
+    }
+
+    /// The name, after how many there are when more than one.
+    pub(crate) fn counted(&self, count: i32) -> String {
+        if count > 1 {
+            format!("{count} {}", self.plural())
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// When they next regain a hit point on their own, pushed back while they
+/// are fighting.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "039b273d-23a2-450a-b451-ba2a5c1313ab"]
+pub(crate) struct NaturalRegeneration {
+    pub next_tick: i64,
+}
+register_component!(NaturalRegeneration);
+
+/// A frame of an animation, drawn over the map from `start_time_ms` until
+/// its `EndTime`. The turns wait for the blocking ones to finish.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "fd6d0a66-0135-4bb3-a6d2-8e3805307143"]
+pub(crate) struct Particle {
+    pub start_time_ms: i64,
+    pub blocking: bool,
+}
+register_component!(Particle);
+
+/// When the player next gets to notice traps without searching, once a turn.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "e56f808a-c642-44b5-a74e-fad7e5932541"]
+pub(crate) struct PassivePerception {
+    pub next_tick: i64,
+}
+register_component!(PassivePerception);
 
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "c186ed8d-325b-4adc-a5de-2ae2a6f0ce25"]
 pub(crate) struct Player {}
-register_serialize!(Player);
+register_component!(Player);
 
 #[derive(Serialize, Deserialize, PartialEq, Copy, Clone, Debug, TypeUuid)]
 #[uuid = "7a11cab0-db87-48b0-acfc-74056cd9a625"]
 pub(crate) struct Position(pub MapPosition);
-register_serialize!(Position);
+register_component!(Position);
 
 impl From<MapPosition> for Position {
     fn from(pos: MapPosition) -> Self {
@@ -123,12 +528,27 @@ impl From<MapPosition> for Position {
     }
 }
 
+/// Eating the item makes one well fed.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "e4ce208b-1be4-43df-a8f1-92d9369cb044"]
+pub(crate) struct ProvidesFood {}
+register_component!(ProvidesFood);
+
+/// How many there are of a stackable item. Stacks of the same item in a
+/// backpack are merged.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "878650b9-c364-47ca-a7e7-f9cdac1d6c3e"]
+pub(crate) struct Quantity {
+    pub count: i32,
+}
+register_component!(Quantity);
+
 #[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
 #[uuid = "6d29666a-c126-44d9-a90d-864890f804ee"]
 pub(crate) struct Ranged {
     pub range: i32,
 }
-register_serialize!(Ranged);
+register_component!(Ranged);
 
 #[derive(Serialize, Deserialize, Clone, TypeUuid)]
 #[uuid = "6315dfee-74b9-42f4-91dc-145b17723c2e"]
@@ -138,7 +558,106 @@ pub(crate) struct Renderable {
     pub bg: RGB,
     pub render_order: i32,
 }
-register_serialize!(Renderable);
+register_component!(Renderable);
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Resistance {
+    /// Takes double damage.
+    Vulnerable,
+    /// Takes half damage.
+    Resistant,
+    /// Takes no damage.
+    Immune,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "04c825f2-ec42-4d4b-8987-4bb142b5a234"]
+pub(crate) struct Resistances {
+    pub resistances: HashMap<DamageType, Resistance>,
+}
+register_component!(Resistances);
+
+impl Resistance {
+    pub(crate) fn apply(self, damage: i32) -> i32 {
+        match self {
+            Resistance::Vulnerable => damage * 2,
+            Resistance::Resistant => (damage + 1) / 2,
+            Resistance::Immune => 0,
+        }
+    }
+}
+
+/// A temporary effect on a creature.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StatusKind {
+    /// Loses hit points every turn.
+    Poison,
+    /// Gains hit points every turn.
+    Regeneration,
+    /// Regains energy twice as fast.
+    Haste,
+    /// Regains energy half as fast.
+    Slow,
+    /// Often moves in a random direction.
+    Confusion,
+}
+
+impl StatusKind {
+    /// As in "you are poisoned".
+    pub(crate) fn adjective(self) -> &'static str {
+        match self {
+            StatusKind::Poison => "poisoned",
+            StatusKind::Regeneration => "regenerating",
+            StatusKind::Haste => "hasted",
+            StatusKind::Slow => "slowed",
+            StatusKind::Confusion => "confused",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct StatusEffect {
+    pub kind: StatusKind,
+    /// The effect wears off at this tick.
+    pub end_tick: i64,
+    /// When the effect acts next, for those that act every turn.
+    pub next_tick: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "a06f538b-3845-408d-b028-b3655701046e"]
+pub(crate) struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+register_component!(StatusEffects);
+
+impl StatusEffects {
+    pub(crate) fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+}
+
+/// Fires on whoever enters the tile.
+#[derive(Serialize, Deserialize, Clone, Debug, TypeUuid)]
+#[uuid = "c338e14f-e66a-48c1-b78d-ca7852b8ce48"]
+pub(crate) struct Trap {
+    pub effect: TrapEffect,
+}
+register_component!(Trap);
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum TrapEffect {
+    /// Inflicts that much damage.
+    Damage(i32),
+    /// Moves the victim somewhere random on the level.
+    Teleport,
+    /// Alerts the monsters within this radius.
+    Alarm(i32),
+    /// Poisons the victim for that many turns.
+    Poison(i32),
+}
 
 #[derive(Serialize, Deserialize, Clone, TypeUuid)]
 #[uuid = "92d745b2-217a-426f-b6e4-2b0c2811fcd9"]
@@ -147,4 +666,4 @@ pub(crate) struct Viewshed {
     pub range: i32,
     pub dirty: bool,
 }
-register_serialize!(Viewshed);
+register_component!(Viewshed);
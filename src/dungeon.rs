@@ -0,0 +1,193 @@
+use crate::components::{InBackpack, Particle, Player, Position, Viewshed};
+use crate::ecs::Ecs;
+use crate::map_builders;
+use crate::positions::MapPosition;
+use crate::resources::{
+    ArchivedLevel, Autosave, Camera, Depth, Levels, Map, OutputQueue, PlayerEntity, PlayerPosition,
+    PlayerTarget, RunSeed, Stream, TileType,
+};
+use ::bincode::Options;
+use ::legion::query::component;
+use ::legion::*;
+use ::legion_typeuuid::SerializableTypeUuid;
+use ::serde::de::DeserializeSeed;
+
+/// Generates a new map for the given depth, with stairs, and spawns its contents.
+///
+/// Returns the map and where the player starts, which is left empty.
+pub(crate) fn generate_level(ecs: &mut Ecs, depth: i32) -> (Map, MapPosition) {
+    let seed = *resource_get!(ecs, RunSeed);
+    let config = crate::raws::get().level(depth);
+    let mut builder = map_builders::builder(config);
+    let mut rng = seed.stream(Stream::Map, depth);
+    let (built, exit) =
+        map_builders::build_map(&mut *builder, &mut rng, config.width, config.height);
+
+    let mut map = built.map;
+    if depth > 1 {
+        let idx = map.map_pos_to_idx(built.start);
+        map.tiles[idx] = TileType::UpStairs;
+    }
+    let idx = map.map_pos_to_idx(exit);
+    map.tiles[idx] = TileType::DownStairs;
+
+    let mut rng = seed.stream(Stream::Spawns, depth);
+    for region in &built.spawn_regions {
+        crate::spawner::spawn_region(ecs, &mut rng, region, depth);
+    }
+    (map, built.start)
+}
+
+/// Moves the player `delta` levels down, or up when negative.
+///
+/// The player and what they carry comes along, everything else is archived
+/// with the map in `Levels`, so the level is as it was left when returning.
+/// If the move fails, the player stays on the current level.
+pub(crate) fn change_level(
+    ecs: &mut Ecs,
+    registry: &Registry<SerializableTypeUuid>,
+    delta: i32,
+) -> crate::Result<()> {
+    let depth = resource_get!(ecs, Depth).0;
+    let new_depth = depth + delta;
+
+    // Arrive on the stairs leading back.
+    let arrival = if delta > 0 {
+        TileType::UpStairs
+    } else {
+        TileType::DownStairs
+    };
+    if let Some(level) = resource_get!(ecs, Levels).0.get(&new_depth) {
+        level
+            .map
+            .find_tile(arrival)
+            .ok_or("The level has no stairs back")?;
+    }
+
+    let entities = archive_entities(ecs, registry)?;
+    let old_map = ecs.resources.remove::<Map>().expect("Resource is expected");
+
+    let (mut map, pos) = match enter_level(ecs, registry, new_depth, arrival) {
+        Ok(level) => level,
+        Err(err) => {
+            // Put the current level back as it was.
+            ecs.resources.insert(old_map);
+            remove_level_entities(ecs);
+            restore_entities(ecs, registry, &entities)?;
+            return Err(err);
+        }
+    };
+    resource_get_mut!(ecs, Levels).0.insert(
+        depth,
+        ArchivedLevel {
+            map: old_map,
+            entities,
+        },
+    );
+
+    // The content index isn't archived in saves.
+    map.reset_content_index();
+    ecs.resources.insert(map);
+    place_player(ecs, pos);
+    crate::rebuild_map_index(ecs);
+
+    ecs.resources.insert(Depth(new_depth));
+    resource_get_mut!(ecs, Autosave).requested = true;
+
+    let output = resource_get!(ecs, OutputQueue);
+    let direction = if delta > 0 { "down" } else { "up" };
+    output.string(format!("You climb {direction} to depth {new_depth}."));
+
+    Ok(())
+}
+
+/// Restores the archived level at `depth`, or generates it the first time.
+///
+/// Returns its map and where on the `arrival` tile the player arrives.
+fn enter_level(
+    ecs: &mut Ecs,
+    registry: &Registry<SerializableTypeUuid>,
+    depth: i32,
+    arrival: TileType,
+) -> crate::Result<(Map, MapPosition)> {
+    let archived = resource_get_mut!(ecs, Levels).0.remove(&depth);
+    let map = match archived {
+        Some(level) => {
+            if let Err(err) = restore_entities(ecs, registry, &level.entities) {
+                resource_get_mut!(ecs, Levels).0.insert(depth, level);
+                return Err(err);
+            }
+            level.map
+        }
+        None => generate_level(ecs, depth).0,
+    };
+    let pos = map
+        .find_tile(arrival)
+        .ok_or("The level has no stairs back")?;
+    Ok((map, pos))
+}
+
+/// Serializes and removes all entities, except the player and their backpack.
+///
+/// Particles still in flight are removed without being archived.
+fn archive_entities(
+    ecs: &mut Ecs,
+    registry: &Registry<SerializableTypeUuid>,
+) -> crate::Result<Vec<u8>> {
+    let filter = !component::<Player>() & !component::<InBackpack>() & !component::<Particle>();
+    let entity_serializer = legion::serialize::Canon::default();
+    let serializable = ecs
+        .world
+        .as_serializable(filter, registry, &entity_serializer);
+    let data = crate::bincode_options().serialize(&serializable)?;
+
+    remove_level_entities(ecs);
+
+    Ok(data)
+}
+
+/// Removes all entities, except the player and their backpack.
+fn remove_level_entities(ecs: &mut Ecs) {
+    let entities = <Entity>::query()
+        .filter(!component::<Player>() & !component::<InBackpack>())
+        .iter(&ecs.world)
+        .copied()
+        .collect::<Vec<_>>();
+    for entity in entities {
+        ecs.world.remove(entity);
+    }
+}
+
+fn restore_entities(
+    ecs: &mut Ecs,
+    registry: &Registry<SerializableTypeUuid>,
+    data: &[u8],
+) -> crate::Result<()> {
+    let entity_serializer = legion::serialize::Canon::default();
+    let mut deser = bincode::Deserializer::from_slice(data, crate::bincode_options());
+    registry
+        .as_deserialize_into_world(&mut ecs.world, &entity_serializer)
+        .deserialize(&mut deser)?;
+    Ok(())
+}
+
+fn place_player(ecs: &mut Ecs, pos: MapPosition) {
+    let player_entity = resource_get!(ecs, PlayerEntity).0;
+    if let Some(mut entry) = ecs.world.entry(player_entity) {
+        if let Ok(position) = entry.get_component_mut::<Position>() {
+            position.0 = pos;
+        }
+        if let Ok(viewshed) = entry.get_component_mut::<Viewshed>() {
+            viewshed.visible_tiles.clear();
+            viewshed.dirty = true;
+        }
+    }
+
+    let player_pos = PlayerPosition(pos);
+    {
+        let map = resource_get!(ecs, Map);
+        resource_get_mut!(ecs, Camera).center(player_pos, &map);
+    }
+    ecs.resources.insert(player_pos);
+    ecs.resources.insert(PlayerTarget::None);
+}
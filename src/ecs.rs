@@ -14,6 +14,20 @@ impl Ecs {
     }
 }
 
+/// Registers a component type for serializing, and lists it in the saves'
+/// `COMPONENTS` section.
+#[macro_export]
+macro_rules! register_component {
+    ($T:ty) => {
+        register_serialize!($T);
+        inventory::submit! {
+            $crate::save_file::SavedComponent {
+                uuid: <$T as ::type_uuid::TypeUuid>::UUID,
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! resource_get_mut {
     ($ecs:expr, $T:ty) => {
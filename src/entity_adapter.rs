@@ -1,4 +1,4 @@
-use crate::components::{Item, Name, Position, Viewshed};
+use crate::components::{Gender, Item, Name, Position, Quantity, Viewshed};
 use crate::resources::GameLog;
 use ::langgen_english::*;
 use ::legion::*;
@@ -47,14 +47,31 @@ impl EntityAdapter<Entity> for EntityAdapterImpl<'_, '_> {
             true
         }}
     }
-    fn gender(&self, _: Entity) -> langgen_english::Gender {
-        // TODO:
-        langgen_english::Gender::Male
+    fn gender(&self, who: Entity) -> langgen_english::Gender {
+        let gender = self
+            .world
+            .entry_ref(who)
+            .ok()
+            .and_then(|e| e.get_component::<Gender>().ok().copied());
+        match gender {
+            Some(Gender::Male) => langgen_english::Gender::Male,
+            Some(Gender::Female) => langgen_english::Gender::Female,
+            Some(Gender::Neuter) => langgen_english::Gender::Neuter,
+            Some(Gender::Plural) => langgen_english::Gender::Plural,
+            None if self.is_thing(who) => langgen_english::Gender::Neuter,
+            None => langgen_english::Gender::Male,
+        }
     }
     fn is_thing(&self, who: Entity) -> bool {
         self.world
             .entry_ref(who).is_ok_and(|e| e.get_component::<Item>().is_ok())
     }
+    fn count(&self, who: Entity) -> u32 {
+        self.world.entry_ref(who).map_or(1, |e| {
+            e.get_component::<Quantity>()
+                .map_or(1, |quantity| quantity.count.max(1) as u32)
+        })
+    }
     fn has_short_proper(&self, who: Entity) -> bool {
         self.world.entry_ref(who).is_ok_and(|e| {
             e.get_component::<Name>().is_ok_and(|n| n.proper_name)
@@ -82,11 +99,23 @@ impl EntityAdapter<Entity> for EntityAdapterImpl<'_, '_> {
         }
         s.push_str("<unknown>");
     }
-    fn append_short_plural_name(&self, _: Entity, _s: &mut String) {
-        todo!()
+    fn append_short_plural_name(&self, who: Entity, s: &mut String) {
+        if let Ok(entry) = self.world.entry_ref(who) {
+            if let Ok(name) = entry.get_component::<Name>() {
+                s.push_str(&name.plural());
+                return;
+            }
+        }
+        s.push_str("<unknown>");
     }
-    fn append_long_plural_name(&self, _: Entity, _s: &mut String) {
-        todo!()
+    fn append_long_plural_name(&self, who: Entity, s: &mut String) {
+        if let Ok(entry) = self.world.entry_ref(who) {
+            if let Ok(name) = entry.get_component::<Name>() {
+                s.push_str(&name.plural());
+                return;
+            }
+        }
+        s.push_str("<unknown>");
     }
     fn write_text(&mut self, text: &str) {
         self.gamelog.write_text(text);
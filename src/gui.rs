@@ -1,9 +1,10 @@
 use crate::components::*;
 use crate::ecs::*;
-use crate::resources::{Camera, GameLog, Map, PlayerEntity, PlayerPosition};
+use crate::resources::{Camera, GameLog, Map, PlayerEntity, PlayerPosition, RunSeed, Time};
 use crate::InventoryType;
 use crate::{Direction, MapPosition, ScreenPosition};
 use bracket_lib::prelude::*;
+use legion::query::component;
 use legion::*;
 
 const BOTTOM_HEIGHT: i32 = 7;
@@ -68,7 +69,7 @@ pub(crate) fn index_to_letter(idx: u8) -> char {
             18 => 's',
             19 => 't',
             20 => 'u',
-            21 => 'b',
+            21 => 'v',
             22 => 'w',
             23 => 'x',
             24 => 'y',
@@ -83,6 +84,8 @@ pub(crate) struct TargetingInfo {
     range: i32,
     last_mouse_point: Point,
     current_point: Point,
+    /// Previewed around the target when there is one.
+    area: Option<(AreaOfEffect, AreaShape)>,
 }
 
 impl TargetingInfo {
@@ -91,9 +94,15 @@ impl TargetingInfo {
             range,
             last_mouse_point: ctx.mouse_point(),
             current_point: start_pos.into(),
+            area: None,
         }
     }
 
+    pub fn with_area(mut self, area: Option<(AreaOfEffect, AreaShape)>) -> Self {
+        self.area = area;
+        self
+    }
+
     pub fn show_targeting(
         &mut self,
         ecs: &mut Ecs,
@@ -160,6 +169,16 @@ impl TargetingInfo {
             }
         }
         if valid_target {
+            if let Some((area, shape)) = &self.area {
+                let player_pos = resource_get!(ecs, PlayerPosition).0;
+                let target = camera.transform_screen_pos(self.current_point.into());
+                for pos in resource_get!(ecs, Map).area(area, *shape, player_pos, target) {
+                    if camera.is_in_view(pos) {
+                        let point = camera.transform_map_pos(pos);
+                        ctx.set_bg(point.x, point.y, RGB::named(ORANGE));
+                    }
+                }
+            }
             ctx.set_bg(self.current_point.x, self.current_point.y, RGB::named(CYAN));
 
             match (ctx.key, ctx.left_click) {
@@ -343,6 +362,131 @@ pub(crate) fn ask_bool(ctx: &mut BTerm, question: &str) -> (ItemMenuResult, bool
     }
 }
 
+pub(crate) fn show_message(ctx: &mut BTerm, title: &str, lines: &[&str]) {
+    let width = lines
+        .iter()
+        .map(|line| line.len() as i32)
+        .fold(title.len() as i32, i32::max);
+    let height = lines.len() as i32;
+    let (x, y) = message_origin(ctx, width, height);
+
+    ctx.draw_box_double(
+        x,
+        y,
+        width + 3,
+        height + 1,
+        RGB::named(YELLOW),
+        RGB::named(BLACK),
+    );
+    ctx.print_color(x + 2, y, RGB::named(YELLOW), RGB::named(BLACK), title);
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print_color(
+            x + 2,
+            y + 1 + i as i32,
+            RGB::named(WHITE),
+            RGB::named(BLACK),
+            line,
+        );
+    }
+}
+
+/// Where `show_message` puts a box with that much text.
+fn message_origin(ctx: &BTerm, width: i32, height: i32) -> (i32, i32) {
+    let (screen_width, screen_height) = ctx.get_char_size();
+    (
+        screen_width as i32 / 2 - width / 2 - 2,
+        screen_height as i32 / 2 - height / 2 - 1,
+    )
+}
+
+/// Asks for a line of text in a message box, below the hint.
+pub(crate) fn ask_text(
+    ctx: &mut BTerm,
+    title: &str,
+    hint: &str,
+    prompt: &str,
+    text: &mut String,
+    max_len: usize,
+) -> ItemMenuResult {
+    let input = " ".repeat(prompt.len() + max_len + 2);
+    let lines = [hint, "", &input];
+    show_message(ctx, title, &lines);
+
+    let width = lines
+        .iter()
+        .map(|line| line.len() as i32)
+        .fold(title.len() as i32, i32::max);
+    let (x, y) = message_origin(ctx, width, lines.len() as i32);
+    edit_text(ctx, x + 2, y + 3, prompt, text, max_len)
+}
+
+/// Translates keys into the characters allowed in names.
+pub(crate) fn key_to_char(shift: bool, key: VirtualKeyCode) -> Option<char> {
+    let c = match key {
+        VirtualKeyCode::Key0 => '0',
+        VirtualKeyCode::Key1 => '1',
+        VirtualKeyCode::Key2 => '2',
+        VirtualKeyCode::Key3 => '3',
+        VirtualKeyCode::Key4 => '4',
+        VirtualKeyCode::Key5 => '5',
+        VirtualKeyCode::Key6 => '6',
+        VirtualKeyCode::Key7 => '7',
+        VirtualKeyCode::Key8 => '8',
+        VirtualKeyCode::Key9 => '9',
+        VirtualKeyCode::Space => ' ',
+        VirtualKeyCode::Minus => '-',
+        _ => {
+            let idx = letter_to_option(shift, key);
+            if idx < 0 {
+                return None;
+            }
+            return Some(index_to_letter(idx as u8));
+        }
+    };
+    Some(c)
+}
+
+/// Lets the player edit a line of text.
+///
+/// Returns `Selected` when ENTER is pressed.
+pub(crate) fn edit_text(
+    ctx: &mut BTerm,
+    x: i32,
+    y: i32,
+    prompt: &str,
+    text: &mut String,
+    max_len: usize,
+) -> ItemMenuResult {
+    match ctx.key {
+        Some(VirtualKeyCode::Return) => return ItemMenuResult::Selected,
+        Some(VirtualKeyCode::Escape) => return ItemMenuResult::Cancel,
+        Some(VirtualKeyCode::Back) => {
+            text.pop();
+        }
+        Some(key) => {
+            if let Some(c) = key_to_char(ctx.shift, key) {
+                if text.len() < max_len {
+                    text.push(c);
+                }
+            }
+        }
+        None => (),
+    }
+
+    ctx.print_color(x, y, RGB::named(YELLOW), RGB::named(BLACK), prompt);
+    let x = x + prompt.len() as i32 + 1;
+    ctx.print_color(x, y, RGB::named(WHITE), RGB::named(BLACK), &*text);
+    ctx.set(
+        x + text.len() as i32,
+        y,
+        RGB::named(BLACK),
+        RGB::named(WHITE),
+        to_cp437(' '),
+    );
+
+    ItemMenuResult::NoResponse
+}
+
 /// For A-Z menus, translates the keys A through Z into 0..25
 pub(crate) fn letter_to_option(shift: bool, key: VirtualKeyCode) -> i32 {
     let val = match key {
@@ -387,12 +531,37 @@ pub(crate) fn show_inventory(
 ) -> (ItemMenuResult, Option<Entity>) {
     let player_entity = resource_get!(ecs, PlayerEntity);
 
-    let mut query = <(Entity, &Name, &InBackpack, &ItemIndex)>::query();
+    let mut query = <(
+        Entity,
+        &Name,
+        &InBackpack,
+        &ItemIndex,
+        Option<&Equippable>,
+        Option<&Equipped>,
+        Option<&Quantity>,
+    )>::query();
 
     let mut inventory: Vec<_> = query
         .iter(&ecs.world)
         .filter(|item| item.2.owner == player_entity.0)
-        .map(|(entity, name, _inbackpack, idx)| (*entity, idx.index, name))
+        .filter(|(_, _, _, _, equippable, equipped, _)| {
+            let slot = equippable.map(|equippable| equippable.slot);
+            match inv_type {
+                InventoryType::Apply | InventoryType::Drop | InventoryType::Throw => true,
+                InventoryType::Wield => slot.is_some_and(EquipmentSlot::is_wielded),
+                InventoryType::Wear => slot.is_some_and(|slot| !slot.is_wielded()),
+                InventoryType::Remove => equipped.is_some(),
+            }
+        })
+        .map(|(entity, name, _in_backpack, idx, _, equipped, quantity)| {
+            let name = name.counted(quantity.map_or(1, |quantity| quantity.count));
+            let name = match equipped.map(|equipped| equipped.slot) {
+                Some(slot) if slot.is_wielded() => format!("{name} (wielded)"),
+                Some(_) => format!("{name} (worn)"),
+                None => name,
+            };
+            (*entity, idx.index, name)
+        })
         .collect();
 
     let count = inventory.len() as i32;
@@ -400,7 +569,14 @@ pub(crate) fn show_inventory(
 
     if count == 0 {
         let gamelog = resource_get!(ecs, crate::resources::OutputQueue);
-        gamelog.s("Your backpack is empty");
+        gamelog.s(match inv_type {
+            InventoryType::Apply | InventoryType::Drop | InventoryType::Throw => {
+                "Your backpack is empty"
+            }
+            InventoryType::Wield => "You have nothing to wield",
+            InventoryType::Wear => "You have nothing to wear",
+            InventoryType::Remove => "You aren't wielding or wearing anything",
+        });
         return (ItemMenuResult::Cancel, None);
     }
 
@@ -416,6 +592,10 @@ pub(crate) fn show_inventory(
     let title = match inv_type {
         InventoryType::Apply => "Use",
         InventoryType::Drop => "Drop",
+        InventoryType::Wield => "Wield",
+        InventoryType::Wear => "Wear",
+        InventoryType::Remove => "Remove",
+        InventoryType::Throw => "Throw",
     };
     ctx.print_color(18, y - 2, RGB::named(YELLOW), RGB::named(BLACK), title);
     ctx.print_color(
@@ -439,7 +619,7 @@ pub(crate) fn show_inventory(
         );
         ctx.set(19, y, RGB::named(WHITE), RGB::named(BLACK), to_cp437(')'));
 
-        ctx.print(21, y, name.name.to_string());
+        ctx.print(21, y, name);
         y += 1;
     }
 
@@ -494,8 +674,13 @@ pub(crate) fn draw_ui(ecs: &Ecs, ctx: &mut BTerm) {
         RGB::named(BLACK),
     );
 
-    let mut query = <(&CombatStats, &Player)>::query();
-    for (stats, _player) in query.iter(&ecs.world) {
+    let mut query = <(
+        &CombatStats,
+        &Player,
+        Option<&HungerClock>,
+        Option<&StatusEffects>,
+    )>::query();
+    for (stats, _player, hunger, effects) in query.iter(&ecs.world) {
         let health = format!(" HP: {} / {} ", stats.hp, stats.max_hp);
         ctx.print_color(
             12,
@@ -522,6 +707,19 @@ pub(crate) fn draw_ui(ecs: &Ecs, ctx: &mut BTerm) {
             bar_color,
             RGB::named(BLACK),
         );
+
+        draw_status_line(ecs, ctx, hunger, effects, screen_height - 1);
+    }
+
+    if let Some(seed) = ecs.resources.get::<RunSeed>() {
+        let seed = format!(" Seed: {} ", seed.0);
+        ctx.print_color(
+            screen_width - seed.len() as i32 - 2,
+            screen_height - 1,
+            RGB::named(GRAY),
+            RGB::named(BLACK),
+            &seed,
+        );
     }
 
     let gamelog = resource_get!(ecs, GameLog);
@@ -530,16 +728,52 @@ pub(crate) fn draw_ui(ecs: &Ecs, ctx: &mut BTerm) {
     draw_tooltips(ecs, ctx);
 }
 
+/// Shows how hungry the player is unless that's normal, then lists the
+/// effects and the turns they have left, on the bottom line.
+fn draw_status_line(
+    ecs: &Ecs,
+    ctx: &mut BTerm,
+    hunger: Option<&HungerClock>,
+    effects: Option<&StatusEffects>,
+    y: i32,
+) {
+    let tick = resource_get!(ecs, Time).tick;
+    let mut x = 2;
+    if let Some(hunger) = hunger.filter(|hunger| hunger.state != HungerState::Normal) {
+        let color = match hunger.state {
+            HungerState::WellFed => GOLD,
+            HungerState::Hungry => ORANGE,
+            _ => RED,
+        };
+        let text = format!(" {} ", hunger.state.name());
+        ctx.print_color(x, y, RGB::named(color), RGB::named(BLACK), &text);
+        x += text.len() as i32;
+    }
+    for effect in effects.iter().flat_map(|effects| &effects.effects) {
+        let color = match effect.kind {
+            StatusKind::Poison => GREEN,
+            StatusKind::Regeneration => PINK,
+            StatusKind::Haste => CYAN,
+            StatusKind::Slow => GRAY,
+            StatusKind::Confusion => MAGENTA,
+        };
+        let turns = (effect.end_tick - tick + 99) / 100;
+        let text = format!(" {} ({turns}) ", effect.kind.adjective());
+        ctx.print_color(x, y, RGB::named(color), RGB::named(BLACK), &text);
+        x += text.len() as i32;
+    }
+}
+
 fn draw_tooltips(ecs: &Ecs, ctx: &mut BTerm) {
     let camera = *resource_get!(ecs, Camera);
     let map = resource_get!(ecs, Map);
 
     let mouse_pos = ctx.mouse_pos();
-    if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height {
+    if !camera.is_in_view(camera.transform_screen_pos(ctx.mouse_point().into())) {
         return;
     }
     let mut tooltip: Vec<String> = Vec::new();
-    let mut query = <(&Name, &Position)>::query();
+    let mut query = <(&Name, &Position)>::query().filter(!component::<Hidden>());
     for (name, position) in query.iter(&ecs.world) {
         let pos = camera.transform_map_pos(position.0);
         if pos.x == mouse_pos.0 && pos.y == mouse_pos.1 && map.is_visible(position.0) {
@@ -4,18 +4,23 @@ bracket_lib::prelude::add_wasm_support!();
 mod ecs;
 
 mod components;
+mod dungeon;
 mod entity_adapter;
 mod gui;
+mod map_builders;
 mod messages;
 mod player;
 mod positions;
 mod queues;
+mod raws;
 mod resources;
+mod save_file;
+mod save_slots;
 mod scenes;
 mod spawner;
 mod systems;
 
-use crate::components::Player;
+use crate::components::{CombatStats, Name, Particle, Player};
 use crate::positions::{Direction, MapPosition, ScreenPosition};
 use crate::resources::OutputQueue;
 use crate::resources::{Camera, GameLog, PlayerEntity};
@@ -23,7 +28,7 @@ use ::bracket_lib::prelude::*;
 use ::legion::Entity;
 use ::legion_typeuuid::collect_registry;
 use bincode::Options;
-use legion::IntoQuery;
+use legion::{IntoQuery, Schedule};
 use legion_typeuuid::SerializableTypeUuid;
 use std::collections::VecDeque;
 use std::io::Read;
@@ -36,6 +41,24 @@ type Result<T> = std::result::Result<T, Box<dyn std::error::Error + 'static + Se
 pub(crate) enum InventoryType {
     Apply,
     Drop,
+    Wield,
+    Wear,
+    Remove,
+    Throw,
+}
+
+/// What is done at the target chosen.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) enum TargetAction {
+    /// Use the item on it.
+    Use(Entity),
+    /// Throw the item at it.
+    Throw(Entity),
+    /// Fire the ammunition at it from the launcher.
+    Fire {
+        launcher: Entity,
+        ammunition: Entity,
+    },
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -46,8 +69,17 @@ pub(crate) enum RunState {
     Tick,
     EnergylessTick,
     ShowInventory(InventoryType),
-    ShowTargeting(gui::TargetingInfo, Entity),
+    ShowTargeting(gui::TargetingInfo, TargetAction),
     SaveGame,
+    /// Take the stairs, down when positive.
+    ChangeLevel(i32),
+    /// Asks which of the open doors next to the player to close.
+    CloseDoor,
+    CharacterSheet,
+    /// Asks which of the items on the player's tile to pick up.
+    PickUp,
+    /// Waits for the animations to finish before taking input.
+    Animating,
 }
 
 struct OuterState {
@@ -59,6 +91,8 @@ pub(crate) struct State {
     ecs: ecs::Ecs,
     registry: legion::Registry<SerializableTypeUuid>,
     old_shift: bool,
+    /// The seed given on the command line, used for new games.
+    seed: Option<resources::RunSeed>,
 }
 
 impl GameState for OuterState {
@@ -104,6 +138,13 @@ impl GameState for OuterState {
         }
 
         self.scene_manager.tick(&mut self.state, ctx);
+
+        // Closing the window mid-game isn't a clean exit, so the autosave
+        // is offered the next time.
+        if ctx.quitting && self.scene_manager.is_empty() {
+            // main_loop doesn't return, so this is the last chance.
+            save_slots::mark_clean_exit_or_complain();
+        }
     }
 }
 
@@ -114,6 +155,18 @@ const SCREEN_WIDTH: i32 = 80;
 const SCREEN_HEIGHT: i32 = 50;
 
 fn main() -> Result<()> {
+    let seed = match seed_from_args() {
+        Ok(seed) => seed,
+        Err(err) => {
+            eprintln!("{err}\nUsage: rouge [--seed <number or text>]");
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = raws::init() {
+        eprintln!("The raw files in assets/raws have problems:\n{err}");
+        std::process::exit(1);
+    }
+
     let mut builder = BTermBuilder::simple(SCREEN_WIDTH, SCREEN_HEIGHT)?
         .with_title("Rouge World")
         .with_font("terminal8x8.png", 8, 8)
@@ -136,6 +189,7 @@ fn main() -> Result<()> {
             ecs: ecs::Ecs::new(),
             registry: collect_registry(),
             old_shift: false,
+            seed,
         },
         scene_manager: scenes::SceneManager::new(),
     };
@@ -146,57 +200,121 @@ fn main() -> Result<()> {
         .insert(Camera::new(SCREEN_WIDTH, SCREEN_HEIGHT - 7));
 
     gs.scene_manager
-        .push(Box::new(scenes::MainMenuScene::new()));
+        .push(Box::new(scenes::MainMenuScene::startup()));
 
     main_loop(context, gs)?;
     Ok(())
 }
 
+/// Reads the `--seed` argument.
+fn seed_from_args() -> Result<Option<resources::RunSeed>> {
+    let mut seed = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let text = args.next().ok_or("--seed needs a value")?;
+                seed = Some(resources::RunSeed::from_text(&text));
+            }
+            _ => return Err(format!("Unknown argument {arg}").into()),
+        }
+    }
+    Ok(seed)
+}
+
 fn bincode_options() -> bincode::DefaultOptions {
     bincode::DefaultOptions::default()
 }
 
-pub(crate) fn new(ecs: &mut ecs::Ecs) {
-    resources::new(ecs);
+pub(crate) fn new(ecs: &mut ecs::Ecs, seed: resources::RunSeed) {
+    ecs.world = legion::World::default();
+    resources::new(ecs, seed);
+}
+
+fn player_summary(ecs: &ecs::Ecs) -> save_file::PlayerSummary {
+    let turn = ecs
+        .resources
+        .get::<resources::Time>()
+        .map_or(0, |time| time.tick);
+    let depth = ecs
+        .resources
+        .get::<resources::Depth>()
+        .map_or(1, |depth| depth.0);
+    let mut query = <(&Name, &CombatStats, &Player)>::query();
+    query
+        .iter(&ecs.world)
+        .next()
+        .map_or_else(Default::default, |(name, stats, _player)| {
+            save_file::PlayerSummary {
+                name: name.name.clone(),
+                hp: stats.hp,
+                max_hp: stats.max_hp,
+                turn,
+                depth,
+            }
+        })
 }
 
 pub(crate) fn save(gs: &State, writer: &mut dyn Write) -> Result<()> {
-    resources::save(&gs.ecs, writer)?;
+    let header = save_file::SaveHeader::new(player_summary(&gs.ecs));
+    let mut writer = save_file::SaveWriter::new(writer, &header)?;
 
-    let entity_serializer = legion::serialize::Canon::default();
+    resources::save(&gs.ecs, &mut writer)?;
 
-    let serializable =
-        gs.ecs
-            .world
-            .as_serializable(legion::query::any(), &gs.registry, &entity_serializer);
-    // let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::fast());
-    bincode_options().serialize_into(writer, &serializable)?;
+    let entity_serializer = legion::serialize::Canon::default();
 
-    Ok(())
+    let serializable = gs.ecs.world.as_serializable(
+        !legion::query::component::<Particle>(),
+        &gs.registry,
+        &entity_serializer,
+    );
+    let data = bincode_options().serialize(&serializable)?;
+    writer.write(
+        save_file::sections::COMPONENTS,
+        &save_file::known_components(),
+    )?;
+    writer.write_raw(save_file::sections::WORLD, &data)?;
+
+    writer.finish()
 }
 
-pub(crate) fn load(ecs: &mut ecs::Ecs, reader: &mut dyn Read) -> Result<()> {
-    // let mut decoder = flate2::read::GzDecoder::new(reader);
+pub(crate) fn load(ecs: &mut ecs::Ecs, reader: &mut dyn Read) -> save_file::LoadResult<()> {
+    let mut reader = save_file::SaveReader::new(reader)?;
 
-    resources::load(ecs, reader)?;
+    resources::load(ecs, &mut reader)?;
     queues::register_queues(&mut ecs.resources);
 
-    let mut deser = bincode::Deserializer::with_reader(reader, bincode_options());
-    let registry = collect_registry();
+    // Older saves don't list their components, unknown ones fail as corrupt.
+    if let Some(saved) = reader.read_optional::<Vec<[u8; 16]>>(save_file::sections::COMPONENTS)? {
+        save_file::check_components(&saved)?;
+    }
+    let data = reader.read_raw(save_file::sections::WORLD)?;
+    let mut deser = bincode::Deserializer::from_slice(&data, bincode_options());
+    let mut registry = collect_registry();
+    registry.on_unknown(legion::serialize::UnknownType::Error);
     use serde::de::DeserializeSeed;
     let entity_serializer = legion::serialize::Canon::default();
     let world = registry
         .as_deserialize(&entity_serializer)
-        .deserialize(&mut deser)
-        .unwrap();
+        .deserialize(&mut deser)?;
     ecs.world = world;
 
+    // The map's content index isn't saved, rebuild it.
+    rebuild_map_index(ecs);
+
     let mut query = <(Entity, &Player)>::query();
 
-    let (entity, _player) = query.iter(&ecs.world).next().expect("Player");
+    let (entity, _player) = query
+        .iter(&ecs.world)
+        .next()
+        .ok_or(save_file::LoadError::MissingPlayer)?;
 
     let output_queue = OutputQueue::new(Mutex::new(VecDeque::new()), *entity);
     output_queue.s("Welcome back.");
+    let game_version = &reader.header().game_version;
+    if game_version != env!("CARGO_PKG_VERSION") {
+        output_queue.string(format!("The game was saved by version {game_version}."));
+    }
     ecs.resources.insert(output_queue);
 
     ecs.resources.insert(PlayerEntity(*entity));
@@ -205,3 +323,12 @@ pub(crate) fn load(ecs: &mut ecs::Ecs, reader: &mut dyn Read) -> Result<()> {
 
     Ok(())
 }
+
+pub(crate) fn rebuild_map_index(ecs: &mut ecs::Ecs) {
+    Schedule::builder()
+        .add_system(systems::map_indexing_clear_system())
+        .add_system(systems::map_indexing_system())
+        .add_system(systems::map_indexing_traps_system())
+        .build()
+        .execute(&mut ecs.world, &mut ecs.resources);
+}
@@ -0,0 +1,78 @@
+use super::{BuiltMap, MapBuilder};
+use crate::positions::MapPosition;
+use crate::resources::Map;
+use ::bracket_lib::prelude::{RandomNumberGenerator, Rect};
+
+/// The map is split in two again and again, and every part gets a room,
+/// so the rooms fill the map evenly.
+pub(crate) struct BspRoomsBuilder;
+
+/// A part isn't split if either half would be smaller than this.
+const MIN_PART: i32 = 10;
+const MIN_ROOM: i32 = 4;
+
+impl MapBuilder for BspRoomsBuilder {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> BuiltMap {
+        let mut map = Map::new(width, height);
+
+        let mut parts = vec![];
+        split(
+            rng,
+            Rect::with_size(0, 0, width - 1, height - 1),
+            &mut parts,
+        );
+
+        // Neighbouring parts follow each other, so connecting each room to
+        // the previous one keeps the corridors short.
+        for part in parts {
+            let (part_w, part_h) = (part.x2 - part.x1, part.y2 - part.y1);
+            let w = rng.range(MIN_ROOM, part_w);
+            let h = rng.range(MIN_ROOM, part_h);
+            let x = part.x1 + rng.range(0, part_w - w);
+            let y = part.y1 + rng.range(0, part_h - h);
+            let room = Rect::with_size(x, y, w, h);
+
+            map.apply_room_to_map(&room);
+            if !map.rooms.is_empty() {
+                let prev_room = map.rooms[map.rooms.len() - 1];
+                map.connect_rooms(rng, &prev_room, &room);
+            }
+            map.rooms.push(room);
+        }
+
+        let center = map.rooms[0].center();
+        let spawn_regions = super::room_regions(&map, map.rooms.iter().skip(1));
+        BuiltMap {
+            map,
+            start: MapPosition {
+                x: center.x,
+                y: center.y,
+            },
+            spawn_regions,
+        }
+    }
+}
+
+/// Splits the part along its longer side, until the parts are too small.
+fn split(rng: &mut RandomNumberGenerator, part: Rect, parts: &mut Vec<Rect>) {
+    let (w, h) = (part.x2 - part.x1, part.y2 - part.y1);
+    if w >= h && w >= MIN_PART * 2 {
+        let left = rng.range(MIN_PART, w - MIN_PART + 1);
+        split(rng, Rect::with_size(part.x1, part.y1, left, h), parts);
+        split(
+            rng,
+            Rect::with_size(part.x1 + left, part.y1, w - left, h),
+            parts,
+        );
+    } else if h >= MIN_PART * 2 {
+        let top = rng.range(MIN_PART, h - MIN_PART + 1);
+        split(rng, Rect::with_size(part.x1, part.y1, w, top), parts);
+        split(
+            rng,
+            Rect::with_size(part.x1, part.y1 + top, w, h - top),
+            parts,
+        );
+    } else {
+        parts.push(part);
+    }
+}
@@ -0,0 +1,70 @@
+use super::{BuiltMap, MapBuilder};
+use crate::resources::{Map, TileType, WallType};
+use ::bracket_lib::prelude::RandomNumberGenerator;
+
+/// Natural looking caves, grown from random noise.
+pub(crate) struct CellularAutomataBuilder;
+
+const ITERATIONS: i32 = 15;
+/// The size of the squares the caves are split into for spawning.
+const REGION_SIZE: i32 = 12;
+
+impl MapBuilder for CellularAutomataBuilder {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> BuiltMap {
+        let mut map = Map::new(width, height);
+
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                if rng.roll_dice(1, 100) > 55 {
+                    let idx = map.xy_to_idx(x, y);
+                    map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+
+        // A tile becomes a wall when most of its neighbours are walls, or
+        // when it is alone in the open.
+        for _i in 0..ITERATIONS {
+            let mut tiles = map.tiles.clone();
+            for y in 1..height - 1 {
+                for x in 1..width - 1 {
+                    let mut walls = 0;
+                    for (dx, dy) in [
+                        (-1, -1),
+                        (0, -1),
+                        (1, -1),
+                        (-1, 0),
+                        (1, 0),
+                        (-1, 1),
+                        (0, 1),
+                        (1, 1),
+                    ] {
+                        if map.tiles[map.xy_to_idx(x + dx, y + dy)] != TileType::Floor {
+                            walls += 1;
+                        }
+                    }
+                    let idx = map.xy_to_idx(x, y);
+                    tiles[idx] = if walls > 4 || walls == 0 {
+                        TileType::Wall(WallType::Cross)
+                    } else {
+                        TileType::Floor
+                    };
+                }
+            }
+            map.tiles = tiles;
+        }
+
+        let start = super::floor_near_center(&map).unwrap_or_else(|| {
+            // Nothing survived, open up a single cave in the middle.
+            let idx = map.xy_to_idx(width / 2, height / 2);
+            map.tiles[idx] = TileType::Floor;
+            super::floor_near_center(&map).unwrap()
+        });
+        let spawn_regions = super::grid_regions(&map, start, REGION_SIZE);
+        BuiltMap {
+            map,
+            start,
+            spawn_regions,
+        }
+    }
+}
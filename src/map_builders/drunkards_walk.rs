@@ -0,0 +1,54 @@
+use super::{BuiltMap, MapBuilder};
+use crate::positions::MapPosition;
+use crate::resources::{Map, TileType};
+use ::bracket_lib::prelude::RandomNumberGenerator;
+
+/// Winding tunnels dug by diggers stumbling around from the start.
+pub(crate) struct DrunkardsWalkBuilder;
+
+/// How many steps a digger takes before giving up.
+const LIFETIME: i32 = 400;
+/// The part of the map that is dug out, in percent.
+const FLOOR_PERCENT: usize = 40;
+/// The size of the squares the tunnels are split into for spawning.
+const REGION_SIZE: i32 = 12;
+
+impl MapBuilder for DrunkardsWalkBuilder {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> BuiltMap {
+        let mut map = Map::new(width, height);
+        let start = MapPosition {
+            x: width / 2,
+            y: height / 2,
+        };
+        let start_idx = map.map_pos_to_idx(start);
+        map.tiles[start_idx] = TileType::Floor;
+
+        let wanted = ((width - 2) * (height - 2)) as usize * FLOOR_PERCENT / 100;
+        let mut dug = 1;
+        while dug < wanted {
+            let mut pos = start;
+            for _step in 0..LIFETIME {
+                let idx = map.map_pos_to_idx(pos);
+                if map.tiles[idx] != TileType::Floor {
+                    map.tiles[idx] = TileType::Floor;
+                    dug += 1;
+                }
+                let (dx, dy) = match rng.roll_dice(1, 4) {
+                    1 => (-1, 0),
+                    2 => (1, 0),
+                    3 => (0, -1),
+                    _ => (0, 1),
+                };
+                pos.x = (pos.x + dx).clamp(1, width - 2);
+                pos.y = (pos.y + dy).clamp(1, height - 2);
+            }
+        }
+
+        let spawn_regions = super::grid_regions(&map, start, REGION_SIZE);
+        BuiltMap {
+            map,
+            start,
+            spawn_regions,
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Map generators, `assets/raws/levels.yaml` picks one for each depth.
+
+mod bsp_rooms;
+mod cellular_automata;
+mod drunkards_walk;
+mod prefab_vaults;
+mod rooms_and_corridors;
+
+use crate::positions::MapPosition;
+use crate::raws::{LevelConfig, MapBuilderKind};
+use crate::resources::{Map, TileType, WallType};
+use ::bracket_lib::prelude::{RandomNumberGenerator, Rect};
+use ::std::collections::VecDeque;
+
+pub(crate) use bsp_rooms::BspRoomsBuilder;
+pub(crate) use cellular_automata::CellularAutomataBuilder;
+pub(crate) use drunkards_walk::DrunkardsWalkBuilder;
+pub(crate) use prefab_vaults::PrefabVaultsBuilder;
+pub(crate) use rooms_and_corridors::RoomsAndCorridorsBuilder;
+
+/// A generated map, and where things go on it.
+pub(crate) struct BuiltMap {
+    pub map: Map,
+    /// Where the player arrives, and the up stairs are.
+    pub start: MapPosition,
+    /// The floor tiles that are filled with monsters and items, one area
+    /// each. The start's area isn't among them.
+    pub spawn_regions: Vec<Vec<MapPosition>>,
+}
+
+pub(crate) trait MapBuilder {
+    /// Carves out a map, `build_map` fixes its walls afterwards.
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> BuiltMap;
+}
+
+/// Creates the builder the level config asks for.
+pub(crate) fn builder(config: &LevelConfig) -> Box<dyn MapBuilder> {
+    let builder: Box<dyn MapBuilder> = match config.builder {
+        MapBuilderKind::RoomsAndCorridors => Box::new(RoomsAndCorridorsBuilder),
+        MapBuilderKind::BspRooms => Box::new(BspRoomsBuilder),
+        MapBuilderKind::CellularAutomata => Box::new(CellularAutomataBuilder),
+        MapBuilderKind::DrunkardsWalk => Box::new(DrunkardsWalkBuilder),
+    };
+    if config.vaults {
+        Box::new(PrefabVaultsBuilder::new(builder))
+    } else {
+        builder
+    }
+}
+
+/// Builds a map with the builder, and does the post-processing all maps need.
+///
+/// Also returns where the down stairs go, as far from the start as possible.
+pub(crate) fn build_map(
+    builder: &mut dyn MapBuilder,
+    rng: &mut RandomNumberGenerator,
+    width: i32,
+    height: i32,
+) -> (BuiltMap, MapPosition) {
+    let mut built = builder.build(rng, width, height);
+
+    // Whatever can't be walked to from the start is useless.
+    let distances = distances_from(&built.map, built.start);
+    for (idx, distance) in distances.iter().enumerate() {
+        if distance.is_none() {
+            built.map.tiles[idx] = TileType::Wall(WallType::Cross);
+        }
+    }
+    let map = &built.map;
+    for region in built.spawn_regions.iter_mut() {
+        region.retain(|pos| map.tiles[map.map_pos_to_idx(*pos)] == TileType::Floor);
+    }
+    built.spawn_regions.retain(|region| !region.is_empty());
+
+    let exit = distances
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, distance)| distance.map(|distance| (idx, distance)))
+        .max_by_key(|(_idx, distance)| *distance)
+        .map(|(idx, _distance)| idx as i32)
+        .expect("the start to be reachable");
+    let exit = MapPosition {
+        x: exit % width,
+        y: exit / width,
+    };
+
+    place_doors(&mut built.map, exit);
+    built.map.fix_walls();
+    built.map.populate_blocked();
+    (built, exit)
+}
+
+/// How many steps it takes to walk to each tile from the start, `None`
+/// when it can't be reached.
+fn distances_from(map: &Map, start: MapPosition) -> Vec<Option<i32>> {
+    let mut distances = vec![None; map.tiles.len()];
+    let mut queue = VecDeque::new();
+    distances[map.map_pos_to_idx(start)] = Some(0);
+    queue.push_back((start, 0));
+    while let Some((pos, distance)) = queue.pop_front() {
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let next = pos + (dx, dy);
+            if next.x < 0 || next.y < 0 || next.x >= map.width || next.y >= map.height {
+                continue;
+            }
+            let idx = map.map_pos_to_idx(next);
+            let walkable = matches!(map.tiles[idx], TileType::Floor | TileType::Door { .. });
+            if walkable && distances[idx].is_none() {
+                distances[idx] = Some(distance + 1);
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Puts closed doors where corridors enter the rooms.
+fn place_doors(map: &mut Map, exit: MapPosition) {
+    let solid = |map: &Map, x, y| {
+        matches!(
+            map.tiles[map.xy_to_idx(x, y)],
+            TileType::Wall(_) | TileType::Stone
+        )
+    };
+    for room in map.rooms.clone() {
+        // The tiles just outside the room, where its walls are.
+        let mut edge = vec![];
+        for x in room.x1 + 1..=room.x2 {
+            edge.push((x, room.y1));
+            edge.push((x, room.y2 + 1));
+        }
+        for y in room.y1 + 1..=room.y2 {
+            edge.push((room.x1, y));
+            edge.push((room.x2 + 1, y));
+        }
+        for (x, y) in edge {
+            if x < 1 || y < 1 || x >= map.width - 1 || y >= map.height - 1 {
+                continue;
+            }
+            let pos = MapPosition { x, y };
+            let idx = map.map_pos_to_idx(pos);
+            // Only one tile wide openings get a door.
+            if pos != exit
+                && map.tiles[idx] == TileType::Floor
+                && ((solid(map, x - 1, y) && solid(map, x + 1, y))
+                    || (solid(map, x, y - 1) && solid(map, x, y + 1)))
+            {
+                map.tiles[idx] = TileType::Door { open: false };
+            }
+        }
+    }
+}
+
+/// The floor tiles in the rooms, one region per room.
+fn room_regions<'a>(map: &Map, rooms: impl Iterator<Item = &'a Rect>) -> Vec<Vec<MapPosition>> {
+    rooms
+        .map(|room| {
+            let mut region = vec![];
+            for y in room.y1 + 1..=room.y2 {
+                for x in room.x1 + 1..=room.x2 {
+                    if map.tiles[map.xy_to_idx(x, y)] == TileType::Floor {
+                        region.push(MapPosition { x, y });
+                    }
+                }
+            }
+            region
+        })
+        .collect()
+}
+
+/// Splits the floor into squares, for maps without rooms.
+fn grid_regions(map: &Map, start: MapPosition, size: i32) -> Vec<Vec<MapPosition>> {
+    let mut regions = vec![];
+    for top in (0..map.height).step_by(size as usize) {
+        for left in (0..map.width).step_by(size as usize) {
+            if (left..left + size).contains(&start.x) && (top..top + size).contains(&start.y) {
+                continue;
+            }
+            let mut region = vec![];
+            for y in top..(top + size).min(map.height) {
+                for x in left..(left + size).min(map.width) {
+                    if map.tiles[map.xy_to_idx(x, y)] == TileType::Floor {
+                        region.push(MapPosition { x, y });
+                    }
+                }
+            }
+            if !region.is_empty() {
+                regions.push(region);
+            }
+        }
+    }
+    regions
+}
+
+/// The floor tile closest to the map's center.
+fn floor_near_center(map: &Map) -> Option<MapPosition> {
+    let center = MapPosition {
+        x: map.width / 2,
+        y: map.height / 2,
+    };
+    map.tiles
+        .iter()
+        .enumerate()
+        .filter(|(_idx, tile)| **tile == TileType::Floor)
+        .map(|(idx, _tile)| MapPosition {
+            x: idx as i32 % map.width,
+            y: idx as i32 / map.width,
+        })
+        .min_by_key(|pos| (pos.x - center.x).pow(2) + (pos.y - center.y).pow(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raws::LevelConfig;
+
+    /// Checks the maps the builder makes, and returns how many doors they have.
+    fn check_builder(kind: MapBuilderKind, vaults: bool) -> usize {
+        let config = LevelConfig {
+            builder: kind,
+            min_depth: 1,
+            max_depth: None,
+            vaults,
+            width: 0,
+            height: 0,
+        };
+        let mut doors = 0;
+        // Larger and smaller than the view, and as small as allowed.
+        for (seed, (width, height)) in (0..20).zip([(160, 90), (40, 30), (30, 30)].iter().cycle()) {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let (built, exit) = build_map(&mut *builder(&config), &mut rng, *width, *height);
+            let map = &built.map;
+            let floor = |pos: MapPosition| map.tiles[map.map_pos_to_idx(pos)] == TileType::Floor;
+
+            assert!(floor(built.start), "{kind:?} {seed}");
+            assert!(floor(exit), "{kind:?} {seed}");
+            assert_ne!(built.start, exit, "{kind:?} {seed}");
+            assert!(!built.spawn_regions.is_empty(), "{kind:?} {seed}");
+            for region in &built.spawn_regions {
+                assert!(region.iter().all(|pos| floor(*pos)), "{kind:?} {seed}");
+            }
+
+            // All the floor can be reached, and the edges are solid.
+            let distances = distances_from(map, built.start);
+            for (idx, tile) in map.tiles.iter().enumerate() {
+                let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+                if *tile == TileType::Floor {
+                    assert!(distances[idx].is_some(), "{kind:?} {seed} {x},{y}");
+                    assert!(x > 0 && y > 0 && x < map.width - 1 && y < map.height - 1);
+                }
+                if *tile == (TileType::Door { open: false }) {
+                    doors += 1;
+                }
+            }
+        }
+        doors
+    }
+
+    #[test]
+    fn rooms_and_corridors() {
+        assert!(check_builder(MapBuilderKind::RoomsAndCorridors, false) > 0);
+    }
+
+    #[test]
+    fn bsp_rooms() {
+        assert!(check_builder(MapBuilderKind::BspRooms, false) > 0);
+    }
+
+    #[test]
+    fn cellular_automata() {
+        assert_eq!(check_builder(MapBuilderKind::CellularAutomata, false), 0);
+    }
+
+    #[test]
+    fn drunkards_walk() {
+        assert_eq!(check_builder(MapBuilderKind::DrunkardsWalk, false), 0);
+    }
+
+    #[test]
+    fn prefab_vaults() {
+        check_builder(MapBuilderKind::RoomsAndCorridors, true);
+        check_builder(MapBuilderKind::CellularAutomata, true);
+    }
+}
@@ -0,0 +1,88 @@
+use super::{BuiltMap, MapBuilder};
+use crate::resources::{TileType, WallType};
+use ::bracket_lib::prelude::{RandomNumberGenerator, Rect};
+
+/// Hand made vaults, `#` is a wall and `.` is floor. Their edges are floor,
+/// so they don't block the way through the space they are put in.
+const VAULTS: &[&str] = &[
+    "\
+.......
+.##.##.
+.#...#.
+.......
+.#...#.
+.##.##.
+.......",
+    "\
+.........
+.#.#.#.#.
+.........
+.#.#.#.#.
+.........",
+    "\
+.......
+.#####.
+.#...#.
+.#.#.#.
+.#...#.
+.##.##.
+.......",
+];
+
+/// How many vaults a map gets at most.
+const MAX_VAULTS: i32 = 2;
+/// How many places are tried for each vault.
+const ATTEMPTS: i32 = 50;
+
+/// Stamps vaults into the open spaces of another builder's map.
+pub(crate) struct PrefabVaultsBuilder {
+    base: Box<dyn MapBuilder>,
+}
+
+impl PrefabVaultsBuilder {
+    pub(crate) fn new(base: Box<dyn MapBuilder>) -> Self {
+        Self { base }
+    }
+}
+
+impl MapBuilder for PrefabVaultsBuilder {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> BuiltMap {
+        let mut built = self.base.build(rng, width, height);
+        let map = &mut built.map;
+
+        let mut used: Vec<Rect> = vec![];
+        for _i in 0..rng.range(1, MAX_VAULTS + 1) {
+            let vault = VAULTS[rng.range(0, VAULTS.len() as i32) as usize];
+            let rows: Vec<&str> = vault.lines().collect();
+            let (w, h) = (rows[0].len() as i32, rows.len() as i32);
+
+            for _attempt in 0..ATTEMPTS {
+                let x = rng.range(1, width - w);
+                let y = rng.range(1, height - h);
+                let area = Rect::with_size(x, y, w, h);
+                let fits = !area.point_in_rect(built.start.into())
+                    && used.iter().all(|other| !other.intersect(&area))
+                    && (y..y + h).all(|y| {
+                        (x..x + w).all(|x| map.tiles[map.xy_to_idx(x, y)] == TileType::Floor)
+                    });
+                if !fits {
+                    continue;
+                }
+
+                for (dy, row) in rows.iter().enumerate() {
+                    for (dx, c) in row.chars().enumerate() {
+                        let idx = map.xy_to_idx(x + dx as i32, y + dy as i32);
+                        map.tiles[idx] = match c {
+                            '#' => TileType::Wall(WallType::Cross),
+                            _ => TileType::Floor,
+                        };
+                    }
+                }
+                used.push(area);
+                break;
+            }
+        }
+
+        built
+    }
+}
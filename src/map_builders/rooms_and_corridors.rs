@@ -0,0 +1,50 @@
+use super::{BuiltMap, MapBuilder};
+use crate::positions::MapPosition;
+use crate::resources::Map;
+use ::bracket_lib::prelude::{RandomNumberGenerator, Rect};
+
+/// Random rooms that don't overlap, each connected to the previous one.
+pub(crate) struct RoomsAndCorridorsBuilder;
+
+impl MapBuilder for RoomsAndCorridorsBuilder {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> BuiltMap {
+        const MAX_ROOMS: i32 = 30;
+        const MIN_SIZE: i32 = 6;
+        const MAX_SIZE: i32 = 10;
+
+        let mut map = Map::new(width, height);
+
+        for _i in 0..MAX_ROOMS {
+            let w = rng.range(MIN_SIZE, MAX_SIZE);
+            let h = rng.range(MIN_SIZE, MAX_SIZE);
+            let x = rng.roll_dice(1, map.width - w - 1) - 1;
+            let y = rng.roll_dice(1, map.height - h - 1) - 1;
+            let new_room = Rect::with_size(x, y, w, h);
+            let mut ok = true;
+            for other_room in map.rooms.iter() {
+                if new_room.intersect(other_room) {
+                    ok = false
+                }
+            }
+            if ok {
+                map.apply_room_to_map(&new_room);
+                if !map.rooms.is_empty() {
+                    let prev_room = map.rooms[map.rooms.len() - 1];
+                    map.connect_rooms(rng, &prev_room, &new_room);
+                }
+                map.rooms.push(new_room);
+            }
+        }
+
+        let center = map.rooms[0].center();
+        let spawn_regions = super::room_regions(&map, map.rooms.iter().skip(1));
+        BuiltMap {
+            map,
+            start: MapPosition {
+                x: center.x,
+                y: center.y,
+            },
+            spawn_regions,
+        }
+    }
+}
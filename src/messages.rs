@@ -1,10 +1,60 @@
+use crate::components::StatusKind;
 use crate::positions::MapPosition;
+use bracket_lib::prelude::{FontCharType, RGB};
 use legion::Entity;
 
+/// Someone ate, and isn't hungry anymore.
+#[derive(Clone, Debug)]
+pub(crate) struct EatFoodMessage {
+    pub who: Entity,
+}
+
+/// Someone stepped onto a tile, for the traps.
+#[derive(Clone, Debug)]
+pub(crate) struct EnteredTileMessage {
+    pub who: Entity,
+    pub pos: MapPosition,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct GainExperienceMessage {
+    pub who: Entity,
+    pub amount: i32,
+}
+
+/// Puts a status effect on the target, or makes it last longer.
+#[derive(Clone, Debug)]
+pub(crate) struct InflictStatusMessage {
+    pub target: Entity,
+    pub kind: StatusKind,
+    pub turns: i32,
+}
+
+/// Animates an effect over the map.
+#[derive(Clone, Debug)]
+pub(crate) enum ParticleMessage {
+    /// Something flying along the path, a tile at a time.
+    Missile {
+        path: Vec<MapPosition>,
+        glyph: FontCharType,
+        fg: RGB,
+    },
+    /// A blast spreading from the center over the tiles, nearest first.
+    Blast {
+        center: MapPosition,
+        tiles: Vec<MapPosition>,
+        fg: RGB,
+    },
+    /// A flash on someone who got hit.
+    Flash { pos: MapPosition, fg: RGB },
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct ReceiveHealthMessage {
     pub target: Entity,
     pub amount: i32,
+    /// Healing at full health raises the max hp, unless it only regenerates.
+    pub raise_max_hp: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -16,6 +66,15 @@ pub(crate) struct RemoveItemMessage {
 pub(crate) struct SufferDamageMessage {
     pub target: Entity,
     pub amount: i32,
+    /// Who did it, if anyone.
+    pub attacker: Option<Entity>,
+}
+
+/// Wield or wear the item, instead of what is in its slot.
+#[derive(Clone, Debug)]
+pub(crate) struct WantsToEquipMessage {
+    pub who: Entity,
+    pub item: Entity,
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +87,8 @@ pub(crate) struct WantsToMeleeMessage {
 pub(crate) struct WantsToDropMessage {
     pub who: Entity,
     pub item: Entity,
+    /// How many of a stack, all of it when `None`.
+    pub count: Option<i32>,
 }
 
 pub(crate) struct WantsToPickupMessage {
@@ -35,6 +96,26 @@ pub(crate) struct WantsToPickupMessage {
     pub item: Entity,
 }
 
+/// Look carefully for hidden things around.
+pub(crate) struct WantsToSearchMessage {
+    pub who: Entity,
+}
+
+/// Throw the item at the target, or fire it from the launcher.
+#[derive(Clone, Debug)]
+pub(crate) struct WantsToThrowMessage {
+    pub who: Entity,
+    pub item: Entity,
+    pub target: MapPosition,
+    pub launcher: Option<Entity>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct WantsToUnequipMessage {
+    pub who: Entity,
+    pub item: Entity,
+}
+
 pub(crate) struct WantsToUseMessage {
     pub who: Entity,
     pub item: Entity,
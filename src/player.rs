@@ -1,19 +1,33 @@
 use crate::{
-    components::{CombatStats, Energy, Item, Position, Viewshed},
-    messages::{WantsToMeleeMessage, WantsToPickupMessage},
-    queues::{WantsToMeleeQueue, WantsToPickupQueue},
-    resources::{Camera, Map, OutputQueue, PlayerEntity, PlayerPosition, PlayerTarget},
+    components::{
+        Ammunition, CombatStats, Energy, Equipped, InBackpack, Item, ItemIndex, Launcher, Position,
+        StatusEffects, Viewshed,
+    },
+    messages::{
+        EnteredTileMessage, WantsToMeleeMessage, WantsToPickupMessage, WantsToSearchMessage,
+    },
+    queues::{EnteredTileQueue, WantsToMeleeQueue, WantsToPickupQueue, WantsToSearchQueue},
+    resources::{Camera, Map, OutputQueue, PlayerEntity, PlayerPosition, PlayerTarget, TileType},
 };
 // use crate::components::*;
 use crate::ecs::Ecs;
-use crate::positions::{Direction, ScreenPosition};
-use crate::{InventoryType, RunState};
+use crate::positions::{Direction, MapPosition, ScreenPosition};
+use crate::{InventoryType, RunState, TargetAction};
 use bracket_lib::prelude::*;
 use legion::*;
 
 pub(crate) fn try_move_player(dir: Direction, ecs: &mut Ecs) -> RunState {
-    let (delta_x, delta_y) = dir.into();
     let player_entity = resource_get!(ecs, PlayerEntity).0;
+    let (dir, stumbled) = {
+        let entry = ecs.world.entry_ref(player_entity).unwrap();
+        let mut rng = resource_get_mut!(ecs, RandomNumberGenerator);
+        if crate::systems::stumbles(entry.get_component::<StatusEffects>().ok(), &mut rng) {
+            (Direction::random(&mut rng), true)
+        } else {
+            (dir, false)
+        }
+    };
+    let (delta_x, delta_y) = dir.into();
 
     let mut ret = RunState::AwaitingInput;
 
@@ -48,7 +62,24 @@ pub(crate) fn try_move_player(dir: Direction, ecs: &mut Ecs) -> RunState {
             }
         }
 
+        let target = MapPosition { x, y };
+        if map.is_closed_door(target) {
+            map.set_door(target, true);
+            crate::systems::mark_viewsheds_dirty(&mut ecs.world);
+            let mut player_entry = ecs.world.entry(player_entity).unwrap();
+            player_entry.get_component_mut::<Energy>().unwrap().energy = -100;
+            resource_get!(ecs, OutputQueue).s("You open the door.");
+            return RunState::EnergylessTick;
+        }
+
         if map.blocked[idx] {
+            if stumbled {
+                // Stumbling into a wall still takes the turn.
+                let mut player_entry = ecs.world.entry(player_entity).unwrap();
+                player_entry.get_component_mut::<Energy>().unwrap().energy = -100;
+                resource_get!(ecs, OutputQueue).s("You stumble.");
+                ret = RunState::EnergylessTick;
+            }
             None
         } else {
             let mut player_entry = ecs.world.entry(player_entity).unwrap();
@@ -68,6 +99,10 @@ pub(crate) fn try_move_player(dir: Direction, ecs: &mut Ecs) -> RunState {
     if let Some(pos) = pos {
         // Update player position:
         ecs.resources.insert(PlayerPosition(pos));
+        resource_get!(ecs, EnteredTileQueue).send(EnteredTileMessage {
+            who: player_entity,
+            pos,
+        });
     }
     ret
 }
@@ -79,36 +114,162 @@ pub(crate) fn get_item(ecs: &mut Ecs) -> RunState {
 
     let mut query = <(Entity, &Position, &Item)>::query();
 
-    let mut found_entity: Option<Entity> = None;
+    let found: Vec<Entity> = query
+        .iter(&ecs.world)
+        .filter(|(_, pos, _)| pos.0 == player_pos)
+        .map(|(item_entity, _, _)| *item_entity)
+        .collect();
+    match found[..] {
+        [] => {
+            output.s("There is nothing you can pickup here!");
 
-    for (item_entity, pos, _item) in query.iter(&ecs.world) {
-        if pos.0 == player_pos {
-            found_entity = Some(*item_entity);
-            break;
+            RunState::AwaitingInput
+        }
+        [found_entity] => {
+            let queue = resource_get!(ecs, WantsToPickupQueue);
+            queue.send(WantsToPickupMessage {
+                who: player_entity,
+                item: found_entity,
+            });
+            RunState::EnergylessTick
         }
+        _ => RunState::PickUp,
     }
-    if let Some(found_entity) = found_entity {
-        let queue = resource_get!(ecs, WantsToPickupQueue);
-        queue.send(WantsToPickupMessage {
-            who: player_entity,
-            item: found_entity,
-        });
-        RunState::EnergylessTick
+}
+
+/// Aims the wielded launcher, loaded with the first of its ammunition in
+/// the backpack.
+fn fire(ecs: &mut Ecs, ctx: &mut BTerm) -> RunState {
+    let player_entity = resource_get!(ecs, PlayerEntity).0;
+    let output = resource_get!(ecs, OutputQueue);
+
+    let launcher = <(Entity, &Equipped, &Launcher)>::query()
+        .iter(&ecs.world)
+        .find(|(_, equipped, _)| equipped.owner == player_entity)
+        .map(|(entity, _, launcher)| (*entity, launcher.ammunition, launcher.range));
+    let Some((launcher, kind, range)) = launcher else {
+        output.s("You aren't wielding anything to fire.");
+        return RunState::AwaitingInput;
+    };
+    let ammunition = <(Entity, &InBackpack, &Ammunition, &ItemIndex)>::query()
+        .iter(&ecs.world)
+        .filter(|(_, in_backpack, ammunition, _)| {
+            in_backpack.owner == player_entity && ammunition.kind == kind
+        })
+        .min_by_key(|(_, _, _, idx)| idx.index)
+        .map(|(entity, _, _, _)| *entity);
+    let Some(ammunition) = ammunition else {
+        output.string(format!("You have no {}.", kind.plural()));
+        return RunState::AwaitingInput;
+    };
+
+    let player_position = resource_get!(ecs, PlayerPosition).0;
+    let start_pos = resource_get!(ecs, Camera).transform_map_pos(player_position);
+    RunState::ShowTargeting(
+        crate::gui::TargetingInfo::new(range, start_pos, ctx),
+        TargetAction::Fire {
+            launcher,
+            ammunition,
+        },
+    )
+}
+
+/// Looks for hidden traps around, which takes a turn.
+fn search(ecs: &mut Ecs) -> RunState {
+    let player_entity = resource_get!(ecs, PlayerEntity).0;
+    resource_get!(ecs, WantsToSearchQueue).send(WantsToSearchMessage { who: player_entity });
+    let mut player_entry = ecs.world.entry(player_entity).unwrap();
+    player_entry.get_component_mut::<Energy>().unwrap().energy = -100;
+    RunState::EnergylessTick
+}
+
+/// Goes down the stairs when `delta` is positive, up otherwise.
+fn take_stairs(ecs: &mut Ecs, delta: i32) -> RunState {
+    let (stairs, direction) = if delta > 0 {
+        (TileType::DownStairs, "down")
     } else {
-        output.s("There is nothing you can pickup here!");
+        (TileType::UpStairs, "up")
+    };
+    let on_stairs = {
+        let map = resource_get!(ecs, Map);
+        let player_pos = resource_get!(ecs, PlayerPosition).0;
+        map.tiles[map.map_pos_to_idx(player_pos)] == stairs
+    };
+    if !on_stairs {
+        let output = resource_get!(ecs, OutputQueue);
+        output.string(format!("There are no stairs {direction} here."));
+        return RunState::AwaitingInput;
+    }
+
+    let player_entity = resource_get!(ecs, PlayerEntity).0;
+    let mut player_entry = ecs.world.entry(player_entity).unwrap();
+    player_entry.get_component_mut::<Energy>().unwrap().energy = -100;
+    RunState::ChangeLevel(delta)
+}
+
+/// Closes the open door next to the player, or asks which one if there
+/// are several.
+fn close_door(ecs: &mut Ecs) -> RunState {
+    let doors: Vec<Direction> = {
+        let map = resource_get!(ecs, Map);
+        let player_pos = resource_get!(ecs, PlayerPosition).0;
+        Direction::iter()
+            .filter(|dir| {
+                let pos = player_pos + **dir;
+                map.in_bounds(pos.into())
+                    && map.tiles[map.map_pos_to_idx(pos)] == TileType::Door { open: true }
+            })
+            .copied()
+            .collect()
+    };
+    match doors[..] {
+        [] => {
+            resource_get!(ecs, OutputQueue).s("There is no open door here.");
+            RunState::AwaitingInput
+        }
+        [dir] => close_door_at(ecs, dir),
+        _ => RunState::CloseDoor,
+    }
+}
 
-        RunState::AwaitingInput
+pub(crate) fn close_door_at(ecs: &mut Ecs, dir: Direction) -> RunState {
+    let player_entity = resource_get!(ecs, PlayerEntity).0;
+    let output = resource_get!(ecs, OutputQueue);
+    {
+        let mut map = resource_get_mut!(ecs, Map);
+        let pos = resource_get!(ecs, PlayerPosition).0 + dir;
+        if !map.in_bounds(pos.into()) {
+            output.s("There is no door there.");
+            return RunState::AwaitingInput;
+        }
+        let idx = map.map_pos_to_idx(pos);
+        if map.tiles[idx] != (TileType::Door { open: true }) {
+            output.s("There is no open door there.");
+            return RunState::AwaitingInput;
+        }
+        if !map.tile_content[idx].is_empty() {
+            output.s("Something is in the way.");
+            return RunState::AwaitingInput;
+        }
+        map.set_door(pos, false);
     }
+    crate::systems::mark_viewsheds_dirty(&mut ecs.world);
+    let mut player_entry = ecs.world.entry(player_entity).unwrap();
+    player_entry.get_component_mut::<Energy>().unwrap().energy = -100;
+    output.s("You close the door.");
+    RunState::EnergylessTick
 }
 
 fn init_auto_walk(ecs: &Ecs, pos: ScreenPosition) {
     let camera = resource_get!(ecs, Camera);
     let map = resource_get!(ecs, Map);
     let map_pos = camera.transform_screen_pos(pos);
-    let idx = map.map_pos_to_idx(map_pos);
 
     let mut target_pos = resource_get_mut!(ecs, PlayerTarget);
-    if camera.is_in_view(map_pos) && map.revealed_tiles[idx] {
+    if camera.is_in_view(map_pos)
+        && map.in_bounds(map_pos.into())
+        && map.revealed_tiles[map.map_pos_to_idx(map_pos)]
+    {
         *target_pos = PlayerTarget::Position(map_pos);
     } else {
         *target_pos = PlayerTarget::None;
@@ -145,7 +306,10 @@ fn get_auto_walk_dest(ecs: &mut Ecs) -> Option<(i32, i32)> {
             let map = resource_get!(ecs, Map);
             let (dx, dy) = dir.into();
             let new_pos = player_pos + (dx, dy);
-            if map.is_exit_valid(new_pos.x, new_pos.y) {
+            // Running stops in front of known traps.
+            if map.is_exit_valid(new_pos.x, new_pos.y)
+                && !map.known_traps[map.map_pos_to_idx(new_pos)]
+            {
                 return Some((dx, dy));
             }
         }
@@ -182,6 +346,21 @@ pub(crate) fn player_input(ecs: &mut Ecs, ctx: &mut BTerm) -> RunState {
             Some(VirtualKeyCode::Q) => {
                 return RunState::SaveGame;
             }
+            Some(VirtualKeyCode::Period) => {
+                clear_auto_walk(ecs);
+                return take_stairs(ecs, 1);
+            }
+            Some(VirtualKeyCode::Comma) => {
+                clear_auto_walk(ecs);
+                return take_stairs(ecs, -1);
+            }
+            Some(VirtualKeyCode::W) => {
+                clear_auto_walk(ecs);
+                return RunState::ShowInventory(InventoryType::Wear);
+            }
+            Some(VirtualKeyCode::C) => {
+                return RunState::CharacterSheet;
+            }
             Some(VirtualKeyCode::X) => {
                 let target = {
                     let map = resource_get!(ecs, Map);
@@ -234,9 +413,15 @@ pub(crate) fn player_input(ecs: &mut Ecs, ctx: &mut BTerm) -> RunState {
                         RunState::EnergylessTick
                     }
                     VirtualKeyCode::Comma => get_item(ecs),
+                    VirtualKeyCode::C => close_door(ecs),
+                    VirtualKeyCode::S => search(ecs),
+                    VirtualKeyCode::F => fire(ecs, ctx),
 
                     VirtualKeyCode::A => RunState::ShowInventory(InventoryType::Apply),
                     VirtualKeyCode::D => RunState::ShowInventory(InventoryType::Drop),
+                    VirtualKeyCode::W => RunState::ShowInventory(InventoryType::Wield),
+                    VirtualKeyCode::R => RunState::ShowInventory(InventoryType::Remove),
+                    VirtualKeyCode::T => RunState::ShowInventory(InventoryType::Throw),
 
                     VirtualKeyCode::Escape => RunState::ReallyQuit,
                     _ => RunState::AwaitingInput,
@@ -1,5 +1,5 @@
 use crate::resources::PlayerPosition;
-use ::bracket_lib::prelude::Point;
+use ::bracket_lib::prelude::{Point, RandomNumberGenerator};
 use ::legion_typeuuid::*;
 use ::serde::*;
 use ::type_uuid::*;
@@ -10,7 +10,7 @@ pub(crate) struct MapPosition {
     pub x: i32,
     pub y: i32,
 }
-register_serialize!(MapPosition);
+register_component!(MapPosition);
 
 impl From<MapPosition> for Point {
     fn from(pos: MapPosition) -> Self {
@@ -18,6 +18,15 @@ impl From<MapPosition> for Point {
     }
 }
 
+impl From<Point> for MapPosition {
+    fn from(point: Point) -> Self {
+        MapPosition {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
 impl From<PlayerPosition> for MapPosition {
     fn from(pos: PlayerPosition) -> Self {
         pos.0
@@ -60,7 +69,7 @@ pub(crate) struct ScreenPosition {
     pub x: i32,
     pub y: i32,
 }
-register_serialize!(ScreenPosition);
+register_component!(ScreenPosition);
 
 impl From<ScreenPosition> for Point {
     fn from(pos: ScreenPosition) -> Point {
@@ -108,6 +117,10 @@ impl Direction {
         ]
         .iter()
     }
+
+    pub(crate) fn random(rng: &mut RandomNumberGenerator) -> Direction {
+        *Direction::iter().nth(rng.range(0, 8)).unwrap()
+    }
 }
 
 fn dir_to_dx_dy(dir: Direction) -> (i32, i32) {
@@ -7,21 +7,39 @@ pub(crate) struct Queue<T> {
     rx: Receiver<T>,
 }
 
+pub(crate) type EatFoodQueue = Queue<EatFoodMessage>;
+pub(crate) type EnteredTileQueue = Queue<EnteredTileMessage>;
+pub(crate) type GainExperienceQueue = Queue<GainExperienceMessage>;
+pub(crate) type InflictStatusQueue = Queue<InflictStatusMessage>;
+pub(crate) type ParticleQueue = Queue<ParticleMessage>;
 pub(crate) type ReceiveHealthQueue = Queue<ReceiveHealthMessage>;
 pub(crate) type RemoveItemQueue = Queue<RemoveItemMessage>;
 pub(crate) type SufferDamageQueue = Queue<SufferDamageMessage>;
 pub(crate) type WantsToDropQueue = Queue<WantsToDropMessage>;
+pub(crate) type WantsToEquipQueue = Queue<WantsToEquipMessage>;
 pub(crate) type WantsToPickupQueue = Queue<WantsToPickupMessage>;
 pub(crate) type WantsToMeleeQueue = Queue<WantsToMeleeMessage>;
+pub(crate) type WantsToSearchQueue = Queue<WantsToSearchMessage>;
+pub(crate) type WantsToThrowQueue = Queue<WantsToThrowMessage>;
+pub(crate) type WantsToUnequipQueue = Queue<WantsToUnequipMessage>;
 pub(crate) type WantsToUseQueue = Queue<WantsToUseMessage>;
 
 pub(crate) fn register_queues(resources: &mut Resources) {
+    resources.insert(EatFoodQueue::new());
+    resources.insert(EnteredTileQueue::new());
+    resources.insert(GainExperienceQueue::new());
+    resources.insert(InflictStatusQueue::new());
+    resources.insert(ParticleQueue::new());
     resources.insert(ReceiveHealthQueue::new());
     resources.insert(RemoveItemQueue::new());
     resources.insert(SufferDamageQueue::new());
     resources.insert(WantsToDropQueue::new());
+    resources.insert(WantsToEquipQueue::new());
     resources.insert(WantsToPickupQueue::new());
     resources.insert(WantsToMeleeQueue::new());
+    resources.insert(WantsToSearchQueue::new());
+    resources.insert(WantsToThrowQueue::new());
+    resources.insert(WantsToUnequipQueue::new());
     resources.insert(WantsToUseQueue::new());
 }
 
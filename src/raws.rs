@@ -0,0 +1,1228 @@
+//! Monster, item and trap definitions, read from the YAML files in `assets/raws`.
+//!
+//! Monsters have these fields:
+//! ```yaml
+//! - name: Lamotte
+//!   proper_name: true # Optional, the name isn't preceded by "the"
+//!   glyph: l
+//!   fg: "#FF0000"     # Colours are "#RRGGBB"
+//!   bg: "#000000"     # Optional, black by default
+//!   hp: 16
+//!   defense: 1
+//!   power: 4
+//!   vision: 8         # How far the monster can see
+//!   xp: 20            # Optional, awarded for killing it, its hp by default
+//!   gender: female    # Optional, male, female, neuter or plural, for the pronouns
+//!   equipment: [sword] # Optional, the items the monster wields and wears
+//!   melee_damage: 1d6 # Optional, the damage dice without a weapon, 1d4 by default
+//!   damage_type: bludgeon # Optional, bludgeon, slash, pierce or fire
+//!   resistances:      # Optional, vulnerable, resistant or immune to damage types
+//!     fire: vulnerable
+//! ```
+//! Items have the same `name`, `proper_name`, `glyph`, `fg` and `bg`
+//! fields, and these optional ones:
+//! ```yaml
+//!   consumable: true  # The item is used up
+//!   stackable: true   # Several of it share a letter in the backpack
+//!   food: true        # Eating it makes one well fed
+//!   heal: 8           # Heals that many hit points
+//!   damage: 20        # Inflicts that much damage
+//!   range: 6          # Is used on a target this far away
+//!   area_of_effect: 3 # Affects everything within this radius of the target
+//!   area_shape: cone  # Optional, burst around the target by default, or a cone
+//!                     # or line that long from the user towards the target
+//!   slot: weapon      # Is wielded when weapon or ranged, or worn when shield, head or body
+//!   power_bonus: 2    # Adds to the melee power when wielded or worn, or a launcher's missiles'
+//!   defense_bonus: 1  # Adds to the defense when wielded or worn
+//!   melee_damage: 1d8+1 # The weapon's damage dice
+//!   missile_damage: 1d4 # The damage when thrown or fired, 1d2 without it
+//!   damage_type: slash # Optional, bludgeon by default
+//!   ammunition: arrow # Is fired from launchers of this kind, arrow or stone
+//!   launcher: {ammunition: arrow, range: 8} # Fires it this far, needs the ranged slot
+//!   status: confusion # Puts poison, regeneration, haste, slow or confusion on the targets
+//!   duration: 4       # For that many turns, needed with status
+//! ```
+//! Traps have the same `name`, `proper_name`, `glyph`, `fg` and `bg`
+//! fields, and exactly one of these:
+//! ```yaml
+//!   damage: 6         # Inflicts that much damage
+//!   teleport: true    # Moves the victim somewhere random on the level
+//!   alarm: 15         # Alerts the monsters within this radius
+//!   poison: 5         # Poisons the victim for that many turns
+//! ```
+//! The spawn tables list what can appear in a room at each depth:
+//! ```yaml
+//! monsters:           # Or items, or traps
+//!   max_per_room: 2   # How many groups a room has at most
+//!   entries:
+//!     - name: Janouch
+//!       weight: 10    # How often it appears, compared to the other entries
+//!       min_depth: 1  # Optional, the first depth it appears at, 1 by default
+//!       max_depth: 5  # Optional, the last depth it appears at
+//!       min_group: 1  # Optional, the smallest group it appears in, 1 by default
+//!       max_group: 2  # Optional, the largest group, 1 by default
+//! ```
+//! The levels list which map builder makes the levels at each depth:
+//! ```yaml
+//! - builder: cellular_automata # Or rooms_and_corridors, bsp_rooms, drunkards_walk
+//!   min_depth: 3  # Optional, 1 by default
+//!   max_depth: 4  # Optional, the entry is used at all deeper levels without it
+//!   vaults: true  # Optional, stamps prefab vaults into the map
+//!   width: 160    # Optional, the map's size, 120 by 60 by default
+//!   height: 90
+//! ```
+
+use crate::components::*;
+use ::bracket_lib::prelude::{RGB, RandomNumberGenerator, to_cp437};
+use ::serde::Deserialize;
+use ::std::collections::{HashMap, HashSet};
+use ::std::sync::OnceLock;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + 'static + Send + Sync>>;
+
+/// A raw file's name and contents.
+type RawFile = (&'static str, &'static str);
+
+const MONSTERS: RawFile = (
+    "monsters.yaml",
+    include_str!("../assets/raws/monsters.yaml"),
+);
+const ITEMS: RawFile = ("items.yaml", include_str!("../assets/raws/items.yaml"));
+const TRAPS: RawFile = ("traps.yaml", include_str!("../assets/raws/traps.yaml"));
+const SPAWN_TABLES: RawFile = (
+    "spawn_tables.yaml",
+    include_str!("../assets/raws/spawn_tables.yaml"),
+);
+
+const LEVELS: RawFile = ("levels.yaml", include_str!("../assets/raws/levels.yaml"));
+
+static RAWS: OnceLock<Raws> = OnceLock::new();
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMonster {
+    name: String,
+    #[serde(default)]
+    proper_name: bool,
+    glyph: String,
+    fg: String,
+    bg: Option<String>,
+    hp: i32,
+    defense: i32,
+    power: i32,
+    vision: i32,
+    xp: Option<i32>,
+    gender: Option<Gender>,
+    #[serde(default)]
+    equipment: Vec<String>,
+    melee_damage: Option<String>,
+    damage_type: Option<DamageType>,
+    #[serde(default)]
+    resistances: HashMap<DamageType, Resistance>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawItem {
+    name: String,
+    #[serde(default)]
+    proper_name: bool,
+    glyph: String,
+    fg: String,
+    bg: Option<String>,
+    #[serde(default)]
+    consumable: bool,
+    #[serde(default)]
+    stackable: bool,
+    #[serde(default)]
+    food: bool,
+    heal: Option<i32>,
+    damage: Option<i32>,
+    range: Option<i32>,
+    area_of_effect: Option<i32>,
+    area_shape: Option<AreaShape>,
+    slot: Option<EquipmentSlot>,
+    power_bonus: Option<i32>,
+    defense_bonus: Option<i32>,
+    melee_damage: Option<String>,
+    missile_damage: Option<String>,
+    damage_type: Option<DamageType>,
+    ammunition: Option<AmmoKind>,
+    launcher: Option<Launcher>,
+    status: Option<StatusKind>,
+    duration: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTrap {
+    name: String,
+    #[serde(default)]
+    proper_name: bool,
+    glyph: String,
+    fg: String,
+    bg: Option<String>,
+    damage: Option<i32>,
+    #[serde(default)]
+    teleport: bool,
+    alarm: Option<i32>,
+    poison: Option<i32>,
+}
+
+#[derive(Clone)]
+pub(crate) struct MonsterDef {
+    pub name: Name,
+    pub renderable: Renderable,
+    pub stats: CombatStats,
+    pub vision: i32,
+    pub xp: ExperienceValue,
+    pub gender: Option<Gender>,
+    /// The names of the items it is spawned with.
+    pub equipment: Vec<String>,
+    pub melee_damage: MeleeDamage,
+    pub resistances: Option<Resistances>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ItemDef {
+    pub name: Name,
+    pub renderable: Renderable,
+    pub consumable: bool,
+    pub stackable: bool,
+    pub food: bool,
+    pub heal: Option<HealthProvider>,
+    pub damage: Option<InflictsDamage>,
+    pub ranged: Option<Ranged>,
+    pub area_of_effect: Option<AreaOfEffect>,
+    pub area_shape: Option<AreaShape>,
+    pub equippable: Option<Equippable>,
+    pub power_bonus: Option<MeleePowerBonus>,
+    pub defense_bonus: Option<DefenseBonus>,
+    pub melee_damage: Option<MeleeDamage>,
+    pub missile_damage: Option<MissileDamage>,
+    pub ammunition: Option<Ammunition>,
+    pub launcher: Option<Launcher>,
+    pub status: Option<InflictsStatus>,
+}
+
+#[derive(Clone)]
+pub(crate) struct TrapDef {
+    pub name: Name,
+    pub renderable: Renderable,
+    pub trap: Trap,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSpawnTables {
+    monsters: SpawnTable,
+    items: SpawnTable,
+    traps: SpawnTable,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SpawnTable {
+    pub max_per_room: i32,
+    pub entries: Vec<SpawnEntry>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SpawnEntry {
+    /// The monster's, item's or trap's name.
+    pub name: String,
+    pub weight: i32,
+    #[serde(default = "one")]
+    pub min_depth: i32,
+    pub max_depth: Option<i32>,
+    #[serde(default = "one")]
+    pub min_group: i32,
+    #[serde(default = "one")]
+    pub max_group: i32,
+}
+
+/// The algorithms that can make a level's map.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MapBuilderKind {
+    RoomsAndCorridors,
+    BspRooms,
+    CellularAutomata,
+    DrunkardsWalk,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LevelConfig {
+    pub builder: MapBuilderKind,
+    #[serde(default = "one")]
+    pub min_depth: i32,
+    pub max_depth: Option<i32>,
+    /// Stamp prefab vaults into the map.
+    #[serde(default)]
+    pub vaults: bool,
+    #[serde(default = "default_map_width")]
+    pub width: i32,
+    #[serde(default = "default_map_height")]
+    pub height: i32,
+}
+
+/// The builders need some room to work in.
+const MIN_MAP_SIZE: i32 = 30;
+
+fn default_map_width() -> i32 {
+    120
+}
+
+fn default_map_height() -> i32 {
+    60
+}
+
+fn one() -> i32 {
+    1
+}
+
+impl SpawnEntry {
+    fn appears_at(&self, depth: i32) -> bool {
+        self.min_depth <= depth && self.max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+}
+
+impl LevelConfig {
+    fn used_at(&self, depth: i32) -> bool {
+        self.min_depth <= depth && self.max_depth.is_none_or(|max_depth| depth <= max_depth)
+    }
+}
+
+impl SpawnTable {
+    /// Chooses one of the entries that appear at the depth, by their weights.
+    pub(crate) fn roll(&self, rng: &mut RandomNumberGenerator, depth: i32) -> Option<&SpawnEntry> {
+        let entries = || self.entries.iter().filter(|entry| entry.appears_at(depth));
+        let total_weight: i32 = entries().map(|entry| entry.weight).sum();
+        if total_weight <= 0 {
+            return None;
+        }
+        let mut roll = rng.roll_dice(1, total_weight);
+        entries().find(|entry| {
+            roll -= entry.weight;
+            roll <= 0
+        })
+    }
+}
+
+pub(crate) struct Raws {
+    pub monsters: Vec<MonsterDef>,
+    pub items: Vec<ItemDef>,
+    pub traps: Vec<TrapDef>,
+    pub monster_spawns: SpawnTable,
+    pub item_spawns: SpawnTable,
+    pub trap_spawns: SpawnTable,
+    pub levels: Vec<LevelConfig>,
+}
+
+/// Reads and checks the raw files, call it before `get`.
+pub(crate) fn init() -> Result<()> {
+    let raws = Raws::parse(MONSTERS, ITEMS, TRAPS, SPAWN_TABLES, LEVELS)?;
+    let _ = RAWS.set(raws);
+    Ok(())
+}
+
+pub(crate) fn get() -> &'static Raws {
+    RAWS.get().expect("raws::init to be called")
+}
+
+/// Collects everything that is wrong with the raw files,
+/// so they can all be fixed at once.
+struct Problems {
+    file: &'static str,
+    list: Vec<String>,
+}
+
+impl Problems {
+    fn add(&mut self, name: &str, problem: String) {
+        self.list
+            .push(format!("{}: {}: {}", self.file, name, problem));
+    }
+
+    fn check(&mut self, name: &str, ok: bool, problem: &str) {
+        if !ok {
+            self.add(name, problem.to_string());
+        }
+    }
+
+    fn positive(&mut self, name: &str, field: &str, value: Option<i32>) {
+        if let Some(value) = value {
+            self.check(name, value > 0, &format!("{field} must be more than 0"));
+        }
+    }
+}
+
+impl Raws {
+    pub(crate) fn monster(&self, name: &str) -> Option<&MonsterDef> {
+        self.monsters.iter().find(|def| def.name.name == name)
+    }
+
+    pub(crate) fn item(&self, name: &str) -> Option<&ItemDef> {
+        self.items.iter().find(|def| def.name.name == name)
+    }
+
+    pub(crate) fn trap(&self, name: &str) -> Option<&TrapDef> {
+        self.traps.iter().find(|def| def.name.name == name)
+    }
+
+    /// How the level at the depth is made, every depth has one.
+    pub(crate) fn level(&self, depth: i32) -> &LevelConfig {
+        self.levels
+            .iter()
+            .find(|level| level.used_at(depth))
+            .expect("levels to cover all depths")
+    }
+
+    fn parse(
+        monsters: RawFile,
+        items: RawFile,
+        traps: RawFile,
+        spawn_tables: RawFile,
+        levels: RawFile,
+    ) -> Result<Self> {
+        let raw_monsters: Vec<RawMonster> =
+            serde_yaml::from_str(monsters.1).map_err(|err| format!("{}: {}", monsters.0, err))?;
+        let raw_items: Vec<RawItem> =
+            serde_yaml::from_str(items.1).map_err(|err| format!("{}: {}", items.0, err))?;
+        let raw_traps: Vec<RawTrap> =
+            serde_yaml::from_str(traps.1).map_err(|err| format!("{}: {}", traps.0, err))?;
+        let raw_spawn_tables: RawSpawnTables = serde_yaml::from_str(spawn_tables.1)
+            .map_err(|err| format!("{}: {}", spawn_tables.0, err))?;
+        let raw_levels: Vec<LevelConfig> =
+            serde_yaml::from_str(levels.1).map_err(|err| format!("{}: {}", levels.0, err))?;
+
+        let mut names = HashSet::new();
+        let mut problems = Problems {
+            file: monsters.0,
+            list: vec![],
+        };
+        if raw_monsters.is_empty() {
+            problems
+                .list
+                .push(format!("{}: there are no monsters", monsters.0));
+        }
+        let mut parsed_monsters = vec![];
+        for raw in raw_monsters {
+            check_name(&mut problems, &mut names, &raw.name);
+            problems.positive(&raw.name, "hp", Some(raw.hp));
+            problems.check(&raw.name, raw.defense >= 0, "defense can't be negative");
+            problems.check(&raw.name, raw.power >= 0, "power can't be negative");
+            problems.positive(&raw.name, "vision", Some(raw.vision));
+            problems.positive(&raw.name, "xp", raw.xp);
+            problems.check(
+                &raw.name,
+                raw.damage_type.is_none() || raw.melee_damage.is_some(),
+                "damage_type needs melee_damage",
+            );
+            let melee_damage = damage(&mut problems, &raw.name, &raw.melee_damage, raw.damage_type)
+                .unwrap_or(MeleeDamage::UNARMED);
+            let renderable = renderable(&mut problems, &raw.name, &raw.glyph, &raw.fg, &raw.bg, 1);
+            parsed_monsters.push(MonsterDef {
+                name: Name {
+                    name: raw.name,
+                    proper_name: raw.proper_name,
+                },
+                renderable,
+                stats: CombatStats {
+                    max_hp: raw.hp,
+                    hp: raw.hp,
+                    defense: raw.defense,
+                    power: raw.power,
+                },
+                vision: raw.vision,
+                xp: ExperienceValue {
+                    xp: raw.xp.unwrap_or(raw.hp),
+                },
+                gender: raw.gender,
+                equipment: raw.equipment,
+                melee_damage,
+                resistances: (!raw.resistances.is_empty()).then_some(Resistances {
+                    resistances: raw.resistances,
+                }),
+            });
+        }
+
+        problems.file = items.0;
+        if raw_items.is_empty() {
+            problems
+                .list
+                .push(format!("{}: there are no items", items.0));
+        }
+        let mut parsed_items = vec![];
+        for raw in raw_items {
+            check_name(&mut problems, &mut names, &raw.name);
+            problems.positive(&raw.name, "heal", raw.heal);
+            problems.positive(&raw.name, "damage", raw.damage);
+            problems.positive(&raw.name, "range", raw.range);
+            problems.positive(&raw.name, "area_of_effect", raw.area_of_effect);
+            problems.check(
+                &raw.name,
+                raw.heal.is_none() || raw.damage.is_none(),
+                "can't both heal and damage",
+            );
+            problems.check(
+                &raw.name,
+                raw.area_of_effect.is_none() || raw.range.is_some(),
+                "area_of_effect needs a range",
+            );
+            problems.check(
+                &raw.name,
+                raw.area_shape.is_none() || raw.area_of_effect.is_some(),
+                "area_shape needs area_of_effect",
+            );
+            problems.positive(&raw.name, "power_bonus", raw.power_bonus);
+            problems.positive(&raw.name, "defense_bonus", raw.defense_bonus);
+            problems.check(
+                &raw.name,
+                raw.slot.is_some() || (raw.power_bonus.is_none() && raw.defense_bonus.is_none()),
+                "the bonuses need a slot",
+            );
+            problems.check(
+                &raw.name,
+                raw.melee_damage.is_none() || raw.slot == Some(EquipmentSlot::Weapon),
+                "melee_damage needs the weapon slot",
+            );
+            problems.check(
+                &raw.name,
+                raw.damage_type.is_none()
+                    || raw.melee_damage.is_some()
+                    || raw.missile_damage.is_some(),
+                "damage_type needs melee_damage or missile_damage",
+            );
+            let missile_damage = damage(
+                &mut problems,
+                &raw.name,
+                &raw.missile_damage,
+                raw.damage_type,
+            );
+            let missile_damage = missile_damage.map(|damage| MissileDamage {
+                dice: damage.dice,
+                damage_type: damage.damage_type,
+            });
+            let melee_damage = damage(&mut problems, &raw.name, &raw.melee_damage, raw.damage_type);
+            problems.check(
+                &raw.name,
+                raw.launcher.is_some() == (raw.slot == Some(EquipmentSlot::Ranged)),
+                "launcher and the ranged slot go together",
+            );
+            problems.positive(
+                &raw.name,
+                "the launcher's range",
+                raw.launcher.as_ref().map(|launcher| launcher.range),
+            );
+            problems.positive(&raw.name, "duration", raw.duration);
+            problems.check(
+                &raw.name,
+                raw.status.is_some() == raw.duration.is_some(),
+                "status and duration go together",
+            );
+            problems.check(
+                &raw.name,
+                !raw.food || raw.consumable,
+                "food must be consumable",
+            );
+            problems.check(
+                &raw.name,
+                !raw.stackable || raw.slot.is_none(),
+                "stackable items can't be wielded or worn",
+            );
+            let renderable = renderable(&mut problems, &raw.name, &raw.glyph, &raw.fg, &raw.bg, 2);
+            parsed_items.push(ItemDef {
+                name: Name {
+                    name: raw.name,
+                    proper_name: raw.proper_name,
+                },
+                renderable,
+                consumable: raw.consumable,
+                stackable: raw.stackable,
+                food: raw.food,
+                heal: raw.heal.map(|heal_amount| HealthProvider { heal_amount }),
+                damage: raw.damage.map(|damage| InflictsDamage { damage }),
+                ranged: raw.range.map(|range| Ranged { range }),
+                area_of_effect: raw.area_of_effect.map(|radius| AreaOfEffect { radius }),
+                area_shape: raw.area_shape,
+                equippable: raw.slot.map(|slot| Equippable { slot }),
+                power_bonus: raw.power_bonus.map(|power| MeleePowerBonus { power }),
+                defense_bonus: raw.defense_bonus.map(|defense| DefenseBonus { defense }),
+                melee_damage,
+                missile_damage,
+                ammunition: raw.ammunition.map(|kind| Ammunition { kind }),
+                launcher: raw.launcher,
+                status: raw
+                    .status
+                    .zip(raw.duration)
+                    .map(|(kind, turns)| InflictsStatus { kind, turns }),
+            });
+        }
+
+        problems.file = monsters.0;
+        for monster in &parsed_monsters {
+            let mut slots = vec![];
+            for name in &monster.equipment {
+                let slot = parsed_items
+                    .iter()
+                    .find(|def| def.name.name == *name)
+                    .map(|def| def.equippable.as_ref().map(|e| e.slot));
+                match slot {
+                    None => problems.add(&monster.name.name, format!("there is no item {name}")),
+                    Some(None) => {
+                        problems.add(&monster.name.name, format!("{name} can't be equipped"))
+                    }
+                    Some(Some(slot)) => {
+                        problems.check(
+                            &monster.name.name,
+                            !slots.contains(&slot),
+                            &format!("{name} goes in an already used slot"),
+                        );
+                        slots.push(slot);
+                    }
+                }
+            }
+        }
+
+        problems.file = traps.0;
+        let mut parsed_traps = vec![];
+        for raw in raw_traps {
+            check_name(&mut problems, &mut names, &raw.name);
+            problems.positive(&raw.name, "damage", raw.damage);
+            problems.positive(&raw.name, "alarm", raw.alarm);
+            problems.positive(&raw.name, "poison", raw.poison);
+            let effects = [
+                raw.damage.map(TrapEffect::Damage),
+                raw.teleport.then_some(TrapEffect::Teleport),
+                raw.alarm.map(TrapEffect::Alarm),
+                raw.poison.map(TrapEffect::Poison),
+            ];
+            let mut effects = effects.into_iter().flatten();
+            let effect = effects.next();
+            problems.check(
+                &raw.name,
+                effect.is_some() && effects.next().is_none(),
+                "needs exactly one of damage, teleport, alarm and poison",
+            );
+            let renderable = renderable(&mut problems, &raw.name, &raw.glyph, &raw.fg, &raw.bg, 3);
+            parsed_traps.push(TrapDef {
+                name: Name {
+                    name: raw.name,
+                    proper_name: raw.proper_name,
+                },
+                renderable,
+                trap: Trap {
+                    effect: effect.unwrap_or(TrapEffect::Teleport),
+                },
+            });
+        }
+
+        problems.file = spawn_tables.0;
+        check_spawn_table(
+            &mut problems,
+            &raw_spawn_tables.monsters,
+            "monster",
+            |name| parsed_monsters.iter().any(|def| def.name.name == name),
+        );
+        check_spawn_table(&mut problems, &raw_spawn_tables.items, "item", |name| {
+            parsed_items.iter().any(|def| def.name.name == name)
+        });
+        check_spawn_table(&mut problems, &raw_spawn_tables.traps, "trap", |name| {
+            parsed_traps.iter().any(|def| def.name.name == name)
+        });
+
+        problems.file = levels.0;
+        check_levels(&mut problems, &raw_levels);
+
+        if !problems.list.is_empty() {
+            return Err(problems.list.join("\n").into());
+        }
+        Ok(Self {
+            monsters: parsed_monsters,
+            items: parsed_items,
+            traps: parsed_traps,
+            monster_spawns: raw_spawn_tables.monsters,
+            item_spawns: raw_spawn_tables.items,
+            trap_spawns: raw_spawn_tables.traps,
+            levels: raw_levels,
+        })
+    }
+}
+
+fn check_spawn_table(
+    problems: &mut Problems,
+    table: &SpawnTable,
+    kind: &str,
+    exists: impl Fn(&str) -> bool,
+) {
+    if table.max_per_room < 0 {
+        problems.list.push(format!(
+            "{}: {kind}s: max_per_room can't be negative",
+            problems.file
+        ));
+    }
+    for entry in &table.entries {
+        let name = &entry.name;
+        problems.check(name, exists(name), &format!("there is no such {kind}"));
+        problems.positive(name, "weight", Some(entry.weight));
+        problems.positive(name, "min_depth", Some(entry.min_depth));
+        problems.check(
+            name,
+            entry
+                .max_depth
+                .is_none_or(|max_depth| max_depth >= entry.min_depth),
+            "max_depth is less than min_depth",
+        );
+        problems.positive(name, "min_group", Some(entry.min_group));
+        problems.check(
+            name,
+            entry.max_group >= entry.min_group,
+            "max_group is less than min_group",
+        );
+    }
+}
+
+fn check_levels(problems: &mut Problems, levels: &[LevelConfig]) {
+    for level in levels {
+        let name = format!("depth {}", level.min_depth);
+        problems.positive(&name, "min_depth", Some(level.min_depth));
+        problems.check(
+            &name,
+            level
+                .max_depth
+                .is_none_or(|max_depth| max_depth >= level.min_depth),
+            "max_depth is less than min_depth",
+        );
+        for (field, value) in [("width", level.width), ("height", level.height)] {
+            problems.check(
+                &name,
+                value >= MIN_MAP_SIZE,
+                &format!("{field} must be at least {MIN_MAP_SIZE}"),
+            );
+        }
+    }
+    // Past the deepest bound an entry must have no max_depth, so all depths are covered.
+    let deepest = levels
+        .iter()
+        .map(|level| level.max_depth.unwrap_or(level.min_depth))
+        .max()
+        .unwrap_or(0);
+    for depth in 1..=deepest + 1 {
+        if !levels.iter().any(|level| level.used_at(depth)) {
+            problems.list.push(format!(
+                "{}: no level is made at depth {depth}",
+                problems.file
+            ));
+        }
+    }
+}
+
+fn check_name(problems: &mut Problems, names: &mut HashSet<String>, name: &str) {
+    problems.check(name, !name.trim().is_empty(), "the name is empty");
+    problems.check(
+        name,
+        names.insert(name.to_string()),
+        "the name is already used",
+    );
+}
+
+fn renderable(
+    problems: &mut Problems,
+    name: &str,
+    glyph: &str,
+    fg: &str,
+    bg: &Option<String>,
+    render_order: i32,
+) -> Renderable {
+    let mut color = |color: &str| {
+        parse_color(color).unwrap_or_else(|err| {
+            problems.add(name, err);
+            RGB::new()
+        })
+    };
+    let fg = color(fg);
+    let bg = color(bg.as_deref().unwrap_or("#000000"));
+    let glyph = parse_glyph(glyph).unwrap_or_else(|err| {
+        problems.add(name, err);
+        0
+    });
+    Renderable {
+        glyph,
+        fg,
+        bg,
+        render_order,
+    }
+}
+
+fn parse_glyph(glyph: &str) -> std::result::Result<u16, String> {
+    let mut chars = glyph.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if to_cp437(c) != 0 => Ok(to_cp437(c)),
+        (Some(_), None) => Err(format!("the glyph {glyph:?} isn't in the font")),
+        _ => Err(format!("the glyph {glyph:?} should be one character")),
+    }
+}
+
+/// Parses the damage dice of a weapon or missile, and their damage type.
+fn damage(
+    problems: &mut Problems,
+    name: &str,
+    dice: &Option<String>,
+    damage_type: Option<DamageType>,
+) -> Option<MeleeDamage> {
+    let dice = parse_dice(dice.as_deref()?)
+        .map_err(|err| problems.add(name, err))
+        .ok()?;
+    Some(MeleeDamage {
+        dice,
+        damage_type: damage_type.unwrap_or(DamageType::Bludgeon),
+    })
+}
+
+/// Parses dice like "1d6", "2d4+1" or "1d8-1".
+fn parse_dice(dice: &str) -> std::result::Result<Dice, String> {
+    let err = || format!("the dice {dice:?} should look like \"2d4+1\"");
+    let (count, rest) = dice.split_once('d').ok_or_else(err)?;
+    let (sides, bonus) = match rest.find(['+', '-']) {
+        Some(i) => rest.split_at(i),
+        None => (rest, "+0"),
+    };
+    let number = |text: &str| text.parse::<i32>().map_err(|_| err());
+    let dice = Dice {
+        count: number(count)?,
+        sides: number(sides)?,
+        bonus: number(bonus)?,
+    };
+    if dice.count < 1 || dice.sides < 1 {
+        return Err(err());
+    }
+    Ok(dice)
+}
+
+/// Parses a "#RRGGBB" colour.
+fn parse_color(color: &str) -> std::result::Result<RGB, String> {
+    let rgb = color
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| format!("the colour {color:?} should look like \"#RRGGBB\""))?;
+    Ok(RGB::from_u8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_files_are_valid() {
+        let raws = Raws::parse(MONSTERS, ITEMS, TRAPS, SPAWN_TABLES, LEVELS).unwrap();
+        assert!(!raws.monsters.is_empty());
+        assert!(!raws.items.is_empty());
+        assert!(!raws.traps.is_empty());
+        assert_eq!(raws.level(1).builder, MapBuilderKind::RoomsAndCorridors);
+        assert_eq!(raws.level(100).min_depth, 5);
+    }
+
+    const NO_SPAWNS: RawFile = (
+        "spawn_tables.yaml",
+        r#"
+monsters:
+  max_per_room: 0
+  entries: []
+items:
+  max_per_room: 0
+  entries: []
+traps:
+  max_per_room: 0
+  entries: []
+"#,
+    );
+
+    #[test]
+    fn report_problems() {
+        let monsters = (
+            "monsters.yaml",
+            r##"
+- name: Orc
+  glyph: o
+  fg: "#00FF00"
+  hp: 0
+  defense: 1
+  power: 4
+  vision: 8
+"##,
+        );
+        let items = (
+            "items.yaml",
+            r##"
+- name: Orc
+  glyph: "!!"
+  fg: red
+  area_of_effect: 2
+- name: Goblin
+  glyph: g
+  fg: "#00FF00"
+  area_shape: cone
+"##,
+        );
+        let err = Raws::parse(monsters, items, TRAPS, NO_SPAWNS, LEVELS)
+            .err()
+            .unwrap()
+            .to_string();
+        let problems: Vec<_> = err.lines().collect();
+        assert_eq!(
+            problems,
+            vec![
+                "monsters.yaml: Orc: hp must be more than 0",
+                "items.yaml: Orc: the name is already used",
+                "items.yaml: Orc: area_of_effect needs a range",
+                "items.yaml: Orc: the colour \"red\" should look like \"#RRGGBB\"",
+                "items.yaml: Orc: the glyph \"!!\" should be one character",
+                "items.yaml: Goblin: area_shape needs area_of_effect",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_unknown_fields() {
+        let items = (
+            "items.yaml",
+            "- name: Orc\n  glyph: o\n  fg: \"#00FF00\"\n  hp: 3\n",
+        );
+        let err = Raws::parse(("monsters.yaml", "[]"), items, TRAPS, NO_SPAWNS, LEVELS)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.starts_with("items.yaml: "), "{err}");
+        assert!(err.contains("hp"), "{err}");
+    }
+
+    #[test]
+    fn report_trap_problems() {
+        let traps = (
+            "traps.yaml",
+            r##"
+- name: pit
+  glyph: "^"
+  fg: "#FF0000"
+  damage: 0
+- name: trapdoor
+  glyph: "^"
+  fg: "#FF0000"
+- name: siren
+  glyph: "^"
+  fg: "#FF0000"
+  teleport: true
+  alarm: 10
+"##,
+        );
+        let err = Raws::parse(MONSTERS, ITEMS, traps, NO_SPAWNS, LEVELS)
+            .err()
+            .unwrap()
+            .to_string();
+        let problems: Vec<_> = err.lines().collect();
+        assert_eq!(
+            problems,
+            vec![
+                "traps.yaml: pit: damage must be more than 0",
+                "traps.yaml: trapdoor: needs exactly one of damage, teleport, alarm and poison",
+                "traps.yaml: siren: needs exactly one of damage, teleport, alarm and poison",
+            ]
+        );
+    }
+
+    const ORC_MONSTERS: &str =
+        "- {name: Orc, glyph: o, fg: \"#00FF00\", hp: 10, defense: 1, power: 4, vision: 8}\n";
+
+    /// The problems reported with these monsters and items, one per line.
+    fn problems(monsters: &'static str, items: &'static str) -> Vec<String> {
+        let monsters = ("monsters.yaml", monsters);
+        let items = ("items.yaml", items);
+        let err = Raws::parse(monsters, items, TRAPS, NO_SPAWNS, LEVELS)
+            .err()
+            .unwrap()
+            .to_string();
+        err.lines().map(str::to_string).collect()
+    }
+
+    /// The problems reported with these items, next to a valid monster.
+    fn item_problems(items: &'static str) -> Vec<String> {
+        problems(ORC_MONSTERS, items)
+    }
+
+    #[test]
+    fn report_equipment_problems() {
+        let monsters = r##"
+- name: Orc
+  glyph: o
+  fg: "#00FF00"
+  hp: 10
+  defense: 1
+  power: 4
+  vision: 8
+  equipment: [club, rock, axe, mace]
+"##;
+        let items = r##"
+- name: rock
+  glyph: "*"
+  fg: "#808080"
+  power_bonus: 1
+- name: axe
+  glyph: "/"
+  fg: "#808080"
+  slot: weapon
+  power_bonus: 0
+- name: mace
+  glyph: "/"
+  fg: "#808080"
+  slot: weapon
+  power_bonus: 2
+"##;
+        assert_eq!(
+            problems(monsters, items),
+            vec![
+                "items.yaml: rock: the bonuses need a slot",
+                "items.yaml: axe: power_bonus must be more than 0",
+                "monsters.yaml: Orc: there is no item club",
+                "monsters.yaml: Orc: rock can't be equipped",
+                "monsters.yaml: Orc: mace goes in an already used slot",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_melee_damage_problems() {
+        let monsters = r##"
+- name: Orc
+  glyph: o
+  fg: "#00FF00"
+  hp: 10
+  defense: 1
+  power: 4
+  vision: 8
+  damage_type: slash
+"##;
+        let items = r##"
+- name: club
+  glyph: "/"
+  fg: "#808080"
+  melee_damage: 1d6
+- name: axe
+  glyph: "/"
+  fg: "#808080"
+  slot: weapon
+  melee_damage: d8
+"##;
+        assert_eq!(
+            problems(monsters, items),
+            vec![
+                "monsters.yaml: Orc: damage_type needs melee_damage",
+                "items.yaml: club: melee_damage needs the weapon slot",
+                "items.yaml: axe: the dice \"d8\" should look like \"2d4+1\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_missile_problems() {
+        let items = r##"
+- name: rock
+  glyph: "*"
+  fg: "#808080"
+  damage_type: bludgeon
+- name: dart
+  glyph: "/"
+  fg: "#808080"
+  missile_damage: "2"
+- name: sling
+  glyph: "}"
+  fg: "#808080"
+  launcher: {ammunition: stone, range: 6}
+- name: bow
+  glyph: "}"
+  fg: "#808080"
+  slot: ranged
+  launcher: {ammunition: arrow, range: 0}
+"##;
+        assert_eq!(
+            item_problems(items),
+            vec![
+                "items.yaml: rock: damage_type needs melee_damage or missile_damage",
+                "items.yaml: dart: the dice \"2\" should look like \"2d4+1\"",
+                "items.yaml: sling: launcher and the ranged slot go together",
+                "items.yaml: bow: the launcher's range must be more than 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_status_problems() {
+        let items = r##"
+- name: dart
+  glyph: "/"
+  fg: "#808080"
+  status: poison
+- name: bad potion
+  glyph: "!"
+  fg: "#808080"
+  status: slow
+  duration: 0
+"##;
+        assert_eq!(
+            item_problems(items),
+            vec![
+                "items.yaml: dart: status and duration go together",
+                "items.yaml: bad potion: duration must be more than 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_food_problems() {
+        assert_eq!(
+            item_problems("- {name: plastic apple, glyph: \"°\", fg: \"#FF0000\", food: true}\n"),
+            vec!["items.yaml: plastic apple: food must be consumable"]
+        );
+    }
+
+    #[test]
+    fn report_stacking_problems() {
+        assert_eq!(
+            item_problems(
+                "- {name: sword, glyph: /, fg: \"#FFFFFF\", slot: weapon, stackable: true}\n"
+            ),
+            vec!["items.yaml: sword: stackable items can't be wielded or worn"]
+        );
+    }
+
+    #[test]
+    fn parse_dice_strings() {
+        let dice = |count, sides, bonus| Dice {
+            count,
+            sides,
+            bonus,
+        };
+        assert_eq!(parse_dice("1d6"), Ok(dice(1, 6, 0)));
+        assert_eq!(parse_dice("2d4+1"), Ok(dice(2, 4, 1)));
+        assert_eq!(parse_dice("1d8-1"), Ok(dice(1, 8, -1)));
+        for bad in ["", "6", "0d6", "1d0", "1d6+", "1d+6", "1d6+-1", "1 d6"] {
+            assert!(parse_dice(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn report_spawn_table_problems() {
+        let spawn_tables = (
+            "spawn_tables.yaml",
+            r#"
+monsters:
+  max_per_room: 2
+  entries:
+    - name: apple
+      weight: 1
+    - name: Lamotte
+      weight: 0
+      min_depth: 3
+      max_depth: 2
+items:
+  max_per_room: 2
+  entries:
+    - name: apple
+      weight: 1
+      min_group: 2
+traps:
+  max_per_room: 1
+  entries:
+    - name: apple
+      weight: 1
+"#,
+        );
+        let err = Raws::parse(MONSTERS, ITEMS, TRAPS, spawn_tables, LEVELS)
+            .err()
+            .unwrap()
+            .to_string();
+        let problems: Vec<_> = err.lines().collect();
+        assert_eq!(
+            problems,
+            vec![
+                "spawn_tables.yaml: apple: there is no such monster",
+                "spawn_tables.yaml: Lamotte: weight must be more than 0",
+                "spawn_tables.yaml: Lamotte: max_depth is less than min_depth",
+                "spawn_tables.yaml: apple: max_group is less than min_group",
+                "spawn_tables.yaml: apple: there is no such trap",
+            ]
+        );
+    }
+
+    #[test]
+    fn report_level_problems() {
+        let levels = (
+            "levels.yaml",
+            r#"
+- builder: cellular_automata
+  max_depth: 2
+- builder: bsp_rooms
+  min_depth: 5
+  max_depth: 4
+  width: 29
+"#,
+        );
+        let err = Raws::parse(MONSTERS, ITEMS, TRAPS, SPAWN_TABLES, levels)
+            .err()
+            .unwrap()
+            .to_string();
+        let problems: Vec<_> = err.lines().collect();
+        assert_eq!(
+            problems,
+            vec![
+                "levels.yaml: depth 5: max_depth is less than min_depth",
+                "levels.yaml: depth 5: width must be at least 30",
+                "levels.yaml: no level is made at depth 3",
+                "levels.yaml: no level is made at depth 4",
+                "levels.yaml: no level is made at depth 5",
+            ]
+        );
+
+        let levels = ("levels.yaml", "- builder: caves\n");
+        let err = Raws::parse(MONSTERS, ITEMS, TRAPS, SPAWN_TABLES, levels)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.starts_with("levels.yaml: "), "{err}");
+    }
+
+    #[test]
+    fn roll_spawns_by_depth() {
+        let entry = |name: &str, weight, min_depth, max_depth| SpawnEntry {
+            name: name.to_string(),
+            weight,
+            min_depth,
+            max_depth,
+            min_group: 1,
+            max_group: 1,
+        };
+        let table = SpawnTable {
+            max_per_room: 1,
+            entries: vec![
+                entry("rat", 1, 1, Some(2)),
+                entry("orc", 3, 2, None),
+                entry("dragon", 1, 5, None),
+            ],
+        };
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mut roll = |depth| {
+            let mut names: Vec<_> = (0..200)
+                .map(|_| table.roll(&mut rng, depth).unwrap().name.clone())
+                .collect();
+            names.sort();
+            names.dedup();
+            names
+        };
+        assert_eq!(roll(1), vec!["rat"]);
+        assert_eq!(roll(2), vec!["orc", "rat"]);
+        assert_eq!(roll(5), vec!["dragon", "orc"]);
+        assert!(table.roll(&mut rng, 0).is_none());
+    }
+}
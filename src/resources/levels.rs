@@ -0,0 +1,25 @@
+use super::Map;
+use ::serde::*;
+use ::std::collections::HashMap;
+
+/// How deep in the dungeon the player is, the first level is 1.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(crate) struct Depth(pub i32);
+
+impl Default for Depth {
+    fn default() -> Self {
+        Depth(1)
+    }
+}
+
+/// A level the player has left, kept so it can be returned to.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ArchivedLevel {
+    pub map: Map,
+    /// The level's entities, serialized the same way as the world is saved.
+    pub entities: Vec<u8>,
+}
+
+/// The levels the player has left, by their depth.
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct Levels(pub HashMap<i32, ArchivedLevel>);
@@ -1,4 +1,4 @@
-use crate::components::Position;
+use crate::components::{AreaOfEffect, AreaShape, Position};
 use crate::ecs::Ecs;
 use crate::positions::{MapPosition, ScreenPosition};
 use crate::resources::Camera;
@@ -8,9 +8,6 @@ use ::legion::*;
 use ::serde::*;
 use std::cmp::{max, min};
 
-pub(crate) const MAP_WIDTH: i32 = 120;
-pub(crate) const MAP_HEIGHT: i32 = 60;
-
 #[derive(Serialize, Deserialize, PartialEq, Copy, Clone, Debug)]
 pub(crate) enum WallType {
     Vertical,          /* - */
@@ -32,6 +29,12 @@ pub(crate) enum TileType {
     Stone,
     Wall(WallType),
     Floor,
+    DownStairs,
+    UpStairs,
+    /// Blocks movement and sight while closed.
+    Door {
+        open: bool,
+    },
 }
 
 #[derive(Serialize, Deserialize, Default, Clone)]
@@ -45,13 +48,21 @@ pub(crate) struct Map {
     pub blocked: Vec<bool>,
     /// Does the tile contain anything that seems dangerous?
     pub dangerous: Vec<bool>,
+    /// Rebuilt by the map indexing systems, so it is not saved.
+    #[serde(skip)]
     pub tile_content: Vec<Vec<Entity>>,
+    /// The traps that were found, also rebuilt by the map indexing systems.
+    #[serde(skip)]
+    pub known_traps: Vec<bool>,
     only_revealed: bool,
 }
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        matches!(self.tiles[idx], TileType::Wall(_) | TileType::Stone)
+        matches!(
+            self.tiles[idx],
+            TileType::Wall(_) | TileType::Stone | TileType::Door { open: false }
+        )
     }
 
     fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
@@ -70,6 +81,9 @@ impl BaseMap for Map {
                 } else {
                     exits.push((self.map_pos_to_idx(pos), 1.01));
                 }
+            } else if self.is_closed_door(pos) {
+                // Opening the door takes a turn.
+                exits.push((self.map_pos_to_idx(pos), 2.0));
             }
         }
 
@@ -110,7 +124,8 @@ impl Map {
             return false;
         }
         let idx = self.xy_to_idx(x, y);
-        if self.only_revealed && !self.revealed_tiles[idx] {
+        // The player doesn't walk into the unknown, or into traps, on purpose.
+        if self.only_revealed && (!self.revealed_tiles[idx] || self.known_traps[idx]) {
             return false;
         }
         !self.blocked[idx]
@@ -124,7 +139,7 @@ impl Map {
         self.only_revealed = false
     }
 
-    fn apply_room_to_map(&mut self, room: &Rect) {
+    pub(crate) fn apply_room_to_map(&mut self, room: &Rect) {
         for y in room.y1 + 1..=room.y2 {
             for x in room.x1 + 1..=room.x2 {
                 let idx = self.xy_to_idx(x, y);
@@ -133,6 +148,24 @@ impl Map {
         }
     }
 
+    /// Digs an L shaped corridor between the rooms' centers.
+    pub(crate) fn connect_rooms(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        from: &Rect,
+        to: &Rect,
+    ) {
+        let prev_pos = from.center();
+        let new_pos = to.center();
+        if rng.range(0, 2) == 1 {
+            self.apply_horizontal_tunnel(prev_pos.x, new_pos.x, prev_pos.y);
+            self.apply_vertical_tunnel(prev_pos.y, new_pos.y, new_pos.x);
+        } else {
+            self.apply_vertical_tunnel(prev_pos.y, new_pos.y, prev_pos.x);
+            self.apply_horizontal_tunnel(prev_pos.x, new_pos.x, new_pos.y);
+        }
+    }
+
     fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
         for x in min(x1, x2)..=max(x1, x2) {
             let idx = self.xy_to_idx(x, y);
@@ -153,10 +186,31 @@ impl Map {
 
     pub fn populate_blocked(&mut self) {
         for (i, tile) in self.tiles.iter_mut().enumerate() {
-            self.blocked[i] = matches!(*tile, TileType::Wall(_) | TileType::Stone);
+            self.blocked[i] = matches!(
+                *tile,
+                TileType::Wall(_) | TileType::Stone | TileType::Door { open: false }
+            );
         }
     }
 
+    /// Is there a closed door at the position? Unrevealed doors don't count
+    /// when searching only the revealed tiles.
+    pub fn is_closed_door(&self, pos: MapPosition) -> bool {
+        if !self.in_bounds(pos.into()) {
+            return false;
+        }
+        let idx = self.map_pos_to_idx(pos);
+        self.tiles[idx] == TileType::Door { open: false }
+            && (!self.only_revealed || self.revealed_tiles[idx])
+    }
+
+    /// Opens or closes the door at the position.
+    pub fn set_door(&mut self, pos: MapPosition, open: bool) {
+        let idx = self.map_pos_to_idx(pos);
+        self.tiles[idx] = TileType::Door { open };
+        self.blocked[idx] = !open;
+    }
+
     pub fn is_solid(&self, pos: Point) -> bool {
         if !self.in_bounds(pos) {
             return true;
@@ -165,11 +219,103 @@ impl Map {
         matches!(self.tiles[idx], TileType::Wall(_) | TileType::Stone)
     }
 
+    /// Where the first tile of the given type is, ie the stairs.
+    pub fn find_tile(&self, tile: TileType) -> Option<MapPosition> {
+        let idx = self.tiles.iter().position(|t| *t == tile)? as i32;
+        Some(MapPosition {
+            x: idx % self.width,
+            y: idx / self.width,
+        })
+    }
+
     pub fn is_visible(&self, pos: MapPosition) -> bool {
         let idx = self.map_pos_to_idx(pos);
         self.visible_tiles[idx]
     }
 
+    /// The tiles a missile flies over from `from` towards `to`, up to the
+    /// first one with something standing in it. It stops short of walls
+    /// and closed doors, so the last tile is where it lands.
+    pub fn line_of_fire(&self, from: MapPosition, to: MapPosition) -> Vec<MapPosition> {
+        let start: Point = from.into();
+        let mut path = vec![];
+        for point in line2d(LineAlg::Bresenham, start, to.into()) {
+            if point == start {
+                continue;
+            }
+            if !self.in_bounds(point) || self.is_opaque(self.point2d_to_index(point)) {
+                break;
+            }
+            path.push(MapPosition {
+                x: point.x,
+                y: point.y,
+            });
+            if !self.tile_content[self.point2d_to_index(point)].is_empty() {
+                break;
+            }
+        }
+        path
+    }
+
+    /// The tiles an area of effect used from `origin` at `target` covers.
+    /// Walls and closed doors shelter what is behind them, and aren't part
+    /// of the area themselves.
+    pub fn area(
+        &self,
+        area: &AreaOfEffect,
+        shape: AreaShape,
+        origin: MapPosition,
+        target: MapPosition,
+    ) -> Vec<MapPosition> {
+        let aim = target - origin;
+        let open = |point: &Point| {
+            self.in_bounds(*point) && !self.is_opaque(self.point2d_to_index(*point))
+        };
+        match shape {
+            AreaShape::Burst => field_of_view(target.into(), area.radius, self)
+                .into_iter()
+                .filter(open)
+                .map(MapPosition::from)
+                .collect(),
+            AreaShape::Cone => {
+                let aim_length2 = aim.x * aim.x + aim.y * aim.y;
+                // Aimed at the user there is no direction to widen in.
+                if aim_length2 == 0 {
+                    return vec![];
+                }
+                field_of_view(origin.into(), area.radius, self)
+                    .into_iter()
+                    .filter(open)
+                    .filter(|point| {
+                        // Within 45 degrees either way of the aim, so the
+                        // cosine of the angle is at least 1/sqrt(2).
+                        let delta = *point - Point::from(origin);
+                        let dot = delta.x * aim.x + delta.y * aim.y;
+                        let length2 = delta.x * delta.x + delta.y * delta.y;
+                        length2 > 0 && dot >= 0 && 2 * dot * dot >= length2 * aim_length2
+                    })
+                    .map(MapPosition::from)
+                    .collect()
+            }
+            AreaShape::Line => {
+                let steps = aim.x.abs().max(aim.y.abs());
+                if steps == 0 {
+                    return vec![];
+                }
+                // Aiming past the target at a multiple of it keeps it on the line.
+                let times = area.radius / steps + 1;
+                let end = origin + Point::new(aim.x * times, aim.y * times);
+                line2d(LineAlg::Bresenham, origin.into(), end.into())
+                    .into_iter()
+                    .filter(|point| *point != origin.into())
+                    .take_while(open)
+                    .take(area.radius as usize)
+                    .map(MapPosition::from)
+                    .collect()
+            }
+        }
+    }
+
     // Create points surrounding (x, y)
     fn points_around(x: i32, y: i32) -> Vec<Point> {
         vec![
@@ -209,7 +355,8 @@ impl Map {
         false
     }
 
-    fn fix_walls(&mut self) {
+    /// Picks the wall tiles' shapes from their neighbours, run after building a map.
+    pub(crate) fn fix_walls(&mut self) {
         /* Remove single walls completely surrounded */
         /* Change single walls completely lonely */
         for y in 0..self.height {
@@ -276,9 +423,19 @@ impl Map {
         for content in self.tile_content.iter_mut() {
             content.clear();
         }
+        for known_trap in self.known_traps.iter_mut() {
+            *known_trap = false;
+        }
+    }
+
+    /// Recreates an empty content index, ie after loading.
+    pub fn reset_content_index(&mut self) {
+        self.tile_content = vec![vec![]; (self.width * self.height) as usize];
+        self.known_traps = vec![false; (self.width * self.height) as usize];
     }
 
-    fn new(width: i32, height: i32) -> Map {
+    /// A map that is all wall.
+    pub(crate) fn new(width: i32, height: i32) -> Map {
         let size = (width * height) as usize;
         let tiles = vec![TileType::Wall(WallType::Cross); size];
         let rooms: Vec<Rect> = Vec::new();
@@ -293,55 +450,11 @@ impl Map {
             blocked: vec![false; size],
             dangerous: vec![false; size],
             tile_content: vec![vec![]; size],
+            known_traps: vec![false; size],
             only_revealed: false,
         }
     }
 
-    pub fn new_map_rooms_and_corridors() -> Map {
-        const MAX_ROOMS: i32 = 30;
-        const MIN_SIZE: i32 = 6;
-        const MAX_SIZE: i32 = 10;
-
-        let mut map = Map::new(MAP_WIDTH, MAP_HEIGHT);
-
-        let mut rng = RandomNumberGenerator::new();
-
-        for _i in 0..MAX_ROOMS {
-            let w = rng.range(MIN_SIZE, MAX_SIZE);
-            let h = rng.range(MIN_SIZE, MAX_SIZE);
-            let x = rng.roll_dice(1, map.width - w - 1) - 1;
-            let y = rng.roll_dice(1, map.height - h - 1) - 1;
-            let new_room = Rect::with_size(x, y, w, h);
-            let mut ok = true;
-            for other_room in map.rooms.iter() {
-                if new_room.intersect(other_room) {
-                    ok = false
-                }
-            }
-            if ok {
-                map.apply_room_to_map(&new_room);
-                if !map.rooms.is_empty() {
-                    let new_pos = new_room.center();
-                    let prev_pos = map.rooms[map.rooms.len() - 1].center();
-                    if rng.range(0, 2) == 1 {
-                        map.apply_horizontal_tunnel(prev_pos.x, new_pos.x, prev_pos.y);
-                        map.apply_vertical_tunnel(prev_pos.y, new_pos.y, new_pos.x);
-                    } else {
-                        map.apply_vertical_tunnel(prev_pos.y, new_pos.y, prev_pos.x);
-                        map.apply_horizontal_tunnel(prev_pos.x, new_pos.x, new_pos.y);
-                    }
-                }
-                map.rooms.push(new_room);
-            }
-        }
-
-        map.fix_walls();
-
-        map.populate_blocked();
-
-        map
-    }
-
     pub(crate) fn find_closest_unknown(&self, pos: MapPosition) -> Option<MapPosition> {
         // TODO: Change to breadth first search, then return the position,
         // use autowalk to get there.
@@ -502,7 +615,7 @@ pub(crate) fn draw_map(ecs: &Ecs, ctx: &mut BTerm) {
                 let tile = map.tiles[idx];
                 // Render a tile depending upon the tile type
                 if map.revealed_tiles[idx] {
-                    let glyph;
+                    let mut glyph;
 
                     let bg = RGB::from_f32(0., 0., 0.);
                     let mut fg;
@@ -520,12 +633,29 @@ pub(crate) fn draw_map(ecs: &Ecs, ctx: &mut BTerm) {
                             fg = RGB::from_f32(0.0, 1.0, 0.0);
                             glyph = to_cp437('#');
                         }
+                        TileType::DownStairs => {
+                            fg = RGB::from_f32(0.0, 1.0, 1.0);
+                            glyph = to_cp437('>');
+                        }
+                        TileType::UpStairs => {
+                            fg = RGB::from_f32(0.0, 1.0, 1.0);
+                            glyph = to_cp437('<');
+                        }
+                        TileType::Door { open } => {
+                            fg = RGB::from_f32(0.6, 0.4, 0.2);
+                            glyph = to_cp437(if open { '\'' } else { '+' });
+                        }
+                    }
+                    // Remembered where it's out of sight, the trap itself is drawn where it's seen.
+                    if map.known_traps[idx] {
+                        fg = RGB::from_f32(1.0, 0.0, 0.0);
+                        glyph = to_cp437('^');
                     }
                     if !map.visible_tiles[idx] {
                         fg = fg.to_greyscale();
                     }
                     for i in 0..crate::LAYERS {
-                        if i == 0 || tile != TileType::Floor {
+                        if i == 0 || map.is_opaque(idx) {
                             ctx.set_active_console(i);
                             let mix = (crate::LAYERS - 1 - i) as f32 / (crate::LAYERS - 1) as f32;
                             let mix = mix / 4.0;
@@ -578,4 +708,131 @@ mod tests {
         assert_eq!(map.tiles[3 * 5 + 3], TileType::Wall(WallType::TeeUp));
         assert_eq!(map.tiles[4 * 5 + 1], TileType::Wall(WallType::Vertical));
     }
+
+    #[test]
+    fn doors() {
+        use super::*;
+        let mut map = Map::new(3, 1);
+        map.tiles = vec![
+            TileType::Floor,
+            TileType::Door { open: false },
+            TileType::Floor,
+        ];
+        map.populate_blocked();
+        let door = MapPosition { x: 1, y: 0 };
+        assert!(map.blocked[1]);
+        assert!(map.is_opaque(1));
+        assert!(map.is_closed_door(door));
+        assert!(!map.is_closed_door(MapPosition { x: 3, y: 0 }));
+
+        // Closed doors are a way through, but a slower one.
+        assert_eq!(map.get_available_exits(0).to_vec(), vec![(1, 2.0)]);
+
+        map.set_door(door, true);
+        assert!(!map.blocked[1]);
+        assert!(!map.is_opaque(1));
+        assert!(!map.is_closed_door(door));
+        assert_eq!(map.get_available_exits(0).to_vec(), vec![(1, 1.0)]);
+
+        // The door's state is saved with the map.
+        let data = bincode::serialize(&map).unwrap();
+        let mut loaded: Map = bincode::deserialize(&data).unwrap();
+        assert_eq!(loaded.tiles[1], TileType::Door { open: true });
+        loaded.set_door(door, false);
+        let data = bincode::serialize(&loaded).unwrap();
+        let loaded: Map = bincode::deserialize(&data).unwrap();
+        assert_eq!(loaded.tiles[1], TileType::Door { open: false });
+    }
+
+    #[test]
+    fn line_of_fire() {
+        use super::*;
+        let mut map = Map::new(6, 1);
+        map.tiles = vec![TileType::Floor; 6];
+        map.tiles[5] = TileType::Wall(WallType::Vertical);
+        let pos = |x| MapPosition { x, y: 0 };
+
+        // Missiles land in front of walls.
+        assert_eq!(
+            map.line_of_fire(pos(0), pos(5)),
+            vec![pos(1), pos(2), pos(3), pos(4)]
+        );
+
+        // And hit the first one in the way.
+        let mut world = World::default();
+        map.tile_content[2].push(world.push(()));
+        assert_eq!(map.line_of_fire(pos(0), pos(4)), vec![pos(1), pos(2)]);
+        assert_eq!(map.line_of_fire(pos(4), pos(0)), vec![pos(3), pos(2)]);
+    }
+
+    #[test]
+    fn areas() {
+        use super::*;
+        /* .......
+         * ...#...
+         * .......
+         */
+        let mut map = Map::new(7, 3);
+        map.tiles = vec![TileType::Floor; 7 * 3];
+        map.tiles[10] = TileType::Wall(WallType::Pilar);
+        let pos = |x, y| MapPosition { x, y };
+        let area = |shape, radius, origin, target| {
+            let mut tiles = map.area(&AreaOfEffect { radius }, shape, origin, target);
+            tiles.sort_by_key(|pos| (pos.y, pos.x));
+            tiles
+        };
+
+        // Bursts go around the target, but not through the wall.
+        let burst = area(AreaShape::Burst, 1, pos(0, 0), pos(4, 1));
+        assert!(burst.contains(&pos(4, 1)));
+        assert!(burst.contains(&pos(5, 1)));
+        assert!(!burst.contains(&pos(3, 1)));
+        assert!(!burst.contains(&pos(6, 1)));
+        let burst = area(AreaShape::Burst, 6, pos(0, 0), pos(0, 1));
+        assert!(burst.contains(&pos(2, 1)));
+        assert!(!burst.contains(&pos(4, 1)));
+
+        // Cones widen from the user, who is spared.
+        let cone = area(AreaShape::Cone, 2, pos(0, 1), pos(1, 1));
+        for tile in [pos(1, 0), pos(1, 1), pos(2, 1), pos(1, 2)] {
+            assert!(cone.contains(&tile));
+        }
+        for tile in [pos(0, 0), pos(0, 1), pos(0, 2)] {
+            assert!(!cone.contains(&tile));
+        }
+        assert_eq!(area(AreaShape::Cone, 2, pos(0, 1), pos(0, 1)), vec![]);
+
+        // Lines go on past the target, up to the wall.
+        assert_eq!(
+            area(AreaShape::Line, 5, pos(0, 1), pos(1, 1)),
+            vec![pos(1, 1), pos(2, 1)]
+        );
+        assert_eq!(
+            area(AreaShape::Line, 3, pos(0, 0), pos(1, 1)),
+            vec![pos(1, 1), pos(2, 2)]
+        );
+        assert_eq!(area(AreaShape::Line, 3, pos(0, 0), pos(0, 0)), vec![]);
+    }
+
+    #[test]
+    fn known_traps() {
+        use super::*;
+        let mut map = Map::new(3, 1);
+        map.tiles = vec![TileType::Floor; 3];
+        map.populate_blocked();
+        map.revealed_tiles = vec![true; 3];
+        map.known_traps[1] = true;
+
+        // Auto walking avoids known traps, other paths don't know of them.
+        map.search_only_revealed();
+        assert!(!map.is_exit_valid(1, 0));
+        map.search_also_revealed();
+        assert!(map.is_exit_valid(1, 0));
+
+        // They aren't saved, the map indexing finds them again.
+        let data = bincode::serialize(&map).unwrap();
+        let mut loaded: Map = bincode::deserialize(&data).unwrap();
+        loaded.reset_content_index();
+        assert_eq!(loaded.known_traps, vec![false; 3]);
+    }
 }
@@ -1,24 +1,28 @@
 mod camera;
 mod gamelog;
+mod levels;
 mod map;
+mod run_seed;
 
 pub(crate) use camera::*;
 pub(crate) use gamelog::*;
+pub(crate) use levels::*;
 pub(crate) use map::*;
+pub(crate) use run_seed::*;
 
 use crate::ecs::*;
 
 use ::bracket_lib::prelude::RandomNumberGenerator;
 use ::bracket_lib::prelude::RED;
 use ::legion::Entity;
+use ::serde::de::DeserializeOwned;
 use ::serde::*;
 use ::std::collections::VecDeque;
-use ::std::io::Read;
-use ::std::io::Write;
 use ::std::sync::Mutex;
 
 use crate::components::Position;
 use crate::positions::{Direction, MapPosition};
+use crate::save_file::{LoadResult, SaveReader, SaveWriter, Section, sections};
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct PlayerEntity(pub Entity);
@@ -39,27 +43,33 @@ impl From<PlayerPosition> for Position {
     }
 }
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
 pub(crate) struct Time {
     pub real_time_ms: i64,
     pub last_real_time_ms: i64,
     pub tick: i64,
 }
 
+/// When the game was last autosaved.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct Autosave {
+    pub last_tick: i64,
+    /// Autosave as soon as possible, ie after a level change.
+    pub requested: bool,
+}
+
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + 'static + Send + Sync>>;
 
-pub(crate) fn new(ecs: &mut Ecs) {
+pub(crate) fn new(ecs: &mut Ecs, seed: RunSeed) {
     ecs.resources.insert(PlayerTarget::None);
 
-    let map = Map::new_map_rooms_and_corridors();
-    let player_pos = map.rooms[0].center();
-
-    ecs.resources.insert(RandomNumberGenerator::new());
+    ecs.resources.insert(seed);
+    ecs.resources.insert(seed.stream(Stream::Game, 0));
     ecs.resources.insert(GameLog::new());
+    ecs.resources.insert(Depth(1));
+    ecs.resources.insert(Levels::default());
 
-    for room in map.rooms.iter().skip(1) {
-        crate::spawner::spawn_room(ecs, room);
-    }
+    let (map, player_pos) = crate::dungeon::generate_level(ecs, 1);
     let player_entity = crate::spawner::player(ecs, player_pos.x, player_pos.y);
 
     let output_queue = OutputQueue::new(Mutex::new(VecDeque::new()), player_entity);
@@ -72,32 +82,41 @@ pub(crate) fn new(ecs: &mut Ecs) {
     });
     {
         let mut camera = resource_get_mut!(ecs, Camera);
-        camera.center(player_pos);
+        camera.center(player_pos, &map);
     }
 
     ecs.resources.insert(map);
     ecs.resources.insert(player_pos);
     ecs.resources.insert(PlayerEntity(player_entity));
+    let last_tick = ecs.resources.get_mut_or_default::<Time>().tick;
+    ecs.resources.insert(Autosave {
+        last_tick,
+        requested: false,
+    });
     crate::queues::register_queues(&mut ecs.resources);
 }
 
-fn save_resource<T: 'static + serde::Serialize>(ecs: &Ecs, writer: &mut dyn Write) -> Result<()> {
+fn save_resource<T: 'static + serde::Serialize>(
+    ecs: &Ecs,
+    writer: &mut SaveWriter,
+    section: Section,
+) -> Result<()> {
     let obj = &*resource_get!(ecs, T);
-    let data = bincode::serialize(&obj)?;
-    writer.write_all(&data.len().to_le_bytes())?;
-    writer.write_all(&data)?;
-
-    Ok(())
+    writer.write(section, obj)
 }
 
-pub(crate) fn save(ecs: &Ecs, writer: &mut dyn Write) -> Result<()> {
-    save_resource::<PlayerTarget>(ecs, writer)?;
-    save_resource::<Map>(ecs, writer)?;
-    save_resource::<RandomNumberGenerator>(ecs, writer)?;
-    save_resource::<GameLog>(ecs, writer)?;
+pub(crate) fn save(ecs: &Ecs, writer: &mut SaveWriter) -> Result<()> {
+    save_resource::<PlayerTarget>(ecs, writer, sections::PLAYER_TARGET)?;
+    save_resource::<Map>(ecs, writer, sections::MAP)?;
+    save_resource::<RandomNumberGenerator>(ecs, writer, sections::RNG)?;
+    save_resource::<GameLog>(ecs, writer, sections::GAME_LOG)?;
     // save_resource::<'static, OutputQueue>(ecs, writer)?;
-    save_resource::<Camera>(ecs, writer)?;
-    save_resource::<PlayerPosition>(ecs, writer)?;
+    save_resource::<Camera>(ecs, writer, sections::CAMERA)?;
+    save_resource::<PlayerPosition>(ecs, writer, sections::PLAYER_POSITION)?;
+    save_resource::<Time>(ecs, writer, sections::TIME)?;
+    save_resource::<Depth>(ecs, writer, sections::DEPTH)?;
+    save_resource::<Levels>(ecs, writer, sections::LEVELS)?;
+    save_resource::<RunSeed>(ecs, writer, sections::SEED)?;
     // save_resource::<PlayerEntity>(ecs, writer)?;
 
     // crate::queues::register_queues(&mut ecs.resources);
@@ -105,32 +124,57 @@ pub(crate) fn save(ecs: &Ecs, writer: &mut dyn Write) -> Result<()> {
     Ok(())
 }
 
-fn load_resource<'de, T: 'static + Deserialize<'de>>(
+fn load_resource<T: 'static + DeserializeOwned>(
     ecs: &mut Ecs,
-    reader: &mut dyn Read,
-) -> Result<()> {
-    let mut data = [0_u8; 8];
-    reader.read_exact(&mut data)?;
-    let len = usize::from_le_bytes(data);
-    let mut data = Box::new(vec![0_u8; len]);
-    reader.read_exact(&mut data)?;
-    dbg!("Loading resource ");
-    let obj = bincode::deserialize::<T>(data.leak())?;
+    reader: &mut SaveReader,
+    section: Section,
+) -> LoadResult<()> {
+    let obj = reader.read::<T>(section)?;
+    ecs.resources.insert::<T>(obj);
 
-    dbg!("Loaded resource ");
+    Ok(())
+}
 
+/// Loads a resource that older saves don't have.
+fn load_resource_or_default<T: 'static + DeserializeOwned + Default>(
+    ecs: &mut Ecs,
+    reader: &mut SaveReader,
+    section: Section,
+) -> LoadResult<()> {
+    let obj = reader.read_optional::<T>(section)?.unwrap_or_default();
     ecs.resources.insert::<T>(obj);
 
     Ok(())
 }
 
-pub(crate) fn load(ecs: &mut Ecs, reader: &mut dyn Read) -> Result<()> {
-    load_resource::<PlayerTarget>(ecs, reader)?;
-    load_resource::<Map>(ecs, reader)?;
-    load_resource::<RandomNumberGenerator>(ecs, reader)?;
-    load_resource::<GameLog>(ecs, reader)?;
-    load_resource::<Camera>(ecs, reader)?;
-    load_resource::<PlayerPosition>(ecs, reader)?;
+pub(crate) fn load(ecs: &mut Ecs, reader: &mut SaveReader) -> LoadResult<()> {
+    load_resource::<PlayerTarget>(ecs, reader, sections::PLAYER_TARGET)?;
+    load_resource::<Map>(ecs, reader, sections::MAP)?;
+    load_resource::<RandomNumberGenerator>(ecs, reader, sections::RNG)?;
+    load_resource::<GameLog>(ecs, reader, sections::GAME_LOG)?;
+    load_resource::<Camera>(ecs, reader, sections::CAMERA)?;
+    load_resource::<PlayerPosition>(ecs, reader, sections::PLAYER_POSITION)?;
+
+    // Keep the real time running, only the game's time is restored.
+    let (real_time_ms, last_real_time_ms) = {
+        let time = ecs.resources.get_mut_or_default::<Time>();
+        (time.real_time_ms, time.last_real_time_ms)
+    };
+    load_resource::<Time>(ecs, reader, sections::TIME)?;
+    let last_tick = {
+        let mut time = resource_get_mut!(ecs, Time);
+        time.real_time_ms = real_time_ms;
+        time.last_real_time_ms = last_real_time_ms;
+        time.tick
+    };
+    ecs.resources.insert(Autosave {
+        last_tick,
+        requested: false,
+    });
+    load_resource_or_default::<Depth>(ecs, reader, sections::DEPTH)?;
+    load_resource_or_default::<Levels>(ecs, reader, sections::LEVELS)?;
+    load_resource_or_default::<RunSeed>(ecs, reader, sections::SEED)?;
+    resource_get_mut!(ecs, Map).reset_content_index();
 
     Ok(())
 }
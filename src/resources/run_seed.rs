@@ -0,0 +1,80 @@
+use ::bracket_lib::prelude::RandomNumberGenerator;
+use ::serde::*;
+
+/// The seed everything random in a run comes from, so the same seed gives
+/// the same dungeon.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct RunSeed(pub u64);
+
+/// What a generator made by `RunSeed::stream` is used for.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Stream {
+    /// The map of the level at a depth.
+    Map,
+    /// The monsters and items of the level at a depth.
+    Spawns,
+    /// Everything that happens while playing.
+    Game,
+}
+
+impl RunSeed {
+    pub(crate) fn random() -> Self {
+        RunSeed(RandomNumberGenerator::new().next_u64())
+    }
+
+    /// Numbers are used as they are, other text is hashed into a seed.
+    pub(crate) fn from_text(text: &str) -> Self {
+        let text = text.trim();
+        RunSeed(
+            text.parse()
+                .unwrap_or_else(|_| text.bytes().fold(0, |hash, b| mix(hash ^ u64::from(b)))),
+        )
+    }
+
+    /// A generator for one use, so each level's map and contents don't
+    /// depend on what else happened before it was made.
+    pub(crate) fn stream(self, stream: Stream, index: i32) -> RandomNumberGenerator {
+        let stream = match stream {
+            Stream::Map => 1,
+            Stream::Spawns => 2,
+            Stream::Game => 3,
+        };
+        RandomNumberGenerator::seeded(mix(mix(self.0 ^ mix(stream)) ^ index as u64))
+    }
+}
+
+/// Scrambles the bits, from splitmix64.
+fn mix(x: u64) -> u64 {
+    let x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_are_reproducible() {
+        let roll = |seed: RunSeed, stream, index| {
+            let mut rng = seed.stream(stream, index);
+            (0..8).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        let seed = RunSeed(1234);
+        assert_eq!(roll(seed, Stream::Map, 1), roll(seed, Stream::Map, 1));
+        assert_ne!(roll(seed, Stream::Map, 1), roll(seed, Stream::Map, 2));
+        assert_ne!(roll(seed, Stream::Map, 1), roll(seed, Stream::Spawns, 1));
+        assert_ne!(
+            roll(seed, Stream::Map, 1),
+            roll(RunSeed(1235), Stream::Map, 1)
+        );
+    }
+
+    #[test]
+    fn seeds_from_text() {
+        assert_eq!(RunSeed::from_text(" 42 "), RunSeed(42));
+        assert_eq!(RunSeed::from_text("rouge"), RunSeed::from_text("rouge"));
+        assert_ne!(RunSeed::from_text("rouge"), RunSeed::from_text("rogue"));
+    }
+}
@@ -0,0 +1,675 @@
+//! The save file container.
+//!
+//! A save file starts with a magic number and the container's format version,
+//! followed by a number of tagged sections and an end marker.
+//! Each section has its own version and a CRC of its data:
+//! ```text
+//! "ROUGESAV" format_version:u32
+//! tag:[u8; 4] version:u32 length:u64 crc:u32 data...
+//! ...
+//! "END " 0:u32 0:u64 0:u32
+//! ```
+//! The first section is always the [`SaveHeader`], so it can be read
+//! without reading the whole file. If the header asks for compression,
+//! everything after it, including the end marker, is gzip compressed.
+//!
+//! When a section's format changes, bump its version and submit a
+//! [`Migration`] that upgrades the data from the old version:
+//! ```ignore
+//! inventory::submit! {
+//!     Migration {
+//!         section: sections::MAP.tag,
+//!         from_version: 1,
+//!         migrate: map_v1_to_v2,
+//!     }
+//! }
+//! ```
+
+use ::flate2::read::GzDecoder;
+use ::flate2::write::GzEncoder;
+use ::serde::de::DeserializeOwned;
+use ::serde::*;
+use ::std::collections::HashMap;
+use ::std::io::{Read, Write};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + 'static + Send + Sync>>;
+
+pub(crate) const MAGIC: [u8; 8] = *b"ROUGESAV";
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+pub(crate) type Tag = [u8; 4];
+
+const END_TAG: Tag = *b"END ";
+
+/// A section's tag together with its current version.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Section {
+    pub tag: Tag,
+    pub version: u32,
+}
+
+pub(crate) mod sections {
+    use super::Section;
+
+    pub(crate) const HEADER: Section = Section {
+        tag: *b"HEAD",
+        version: 3,
+    };
+    pub(crate) const PLAYER_TARGET: Section = Section {
+        tag: *b"PTGT",
+        version: 1,
+    };
+    pub(crate) const MAP: Section = Section {
+        tag: *b"MAP ",
+        version: 1,
+    };
+    pub(crate) const RNG: Section = Section {
+        tag: *b"RNG ",
+        version: 1,
+    };
+    pub(crate) const GAME_LOG: Section = Section {
+        tag: *b"GLOG",
+        version: 1,
+    };
+    pub(crate) const CAMERA: Section = Section {
+        tag: *b"CAM ",
+        version: 1,
+    };
+    pub(crate) const PLAYER_POSITION: Section = Section {
+        tag: *b"PPOS",
+        version: 1,
+    };
+    pub(crate) const TIME: Section = Section {
+        tag: *b"TIME",
+        version: 1,
+    };
+    pub(crate) const WORLD: Section = Section {
+        tag: *b"WRLD",
+        version: 1,
+    };
+    pub(crate) const DEPTH: Section = Section {
+        tag: *b"DPTH",
+        version: 1,
+    };
+    pub(crate) const LEVELS: Section = Section {
+        tag: *b"LVLS",
+        version: 1,
+    };
+    pub(crate) const SEED: Section = Section {
+        tag: *b"SEED",
+        version: 1,
+    };
+    pub(crate) const COMPONENTS: Section = Section {
+        tag: *b"CMPS",
+        version: 1,
+    };
+}
+
+/// Upgrades a section's data from `from_version` to `from_version + 1`.
+pub(crate) struct Migration {
+    pub section: Tag,
+    pub from_version: u32,
+    pub migrate: fn(Vec<u8>) -> Result<Vec<u8>>,
+}
+
+inventory::collect!(Migration);
+
+/// A component type that can be saved, submitted by `register_component!`.
+pub(crate) struct SavedComponent {
+    pub uuid: [u8; 16],
+}
+
+inventory::collect!(SavedComponent);
+
+/// The uuids of the component types this game can save and load.
+pub(crate) fn known_components() -> Vec<[u8; 16]> {
+    inventory::iter::<SavedComponent>
+        .into_iter()
+        .map(|component| component.uuid)
+        .collect()
+}
+
+/// Fails if a save lists component types this game doesn't know.
+pub(crate) fn check_components(saved: &[[u8; 16]]) -> LoadResult<()> {
+    let known = known_components();
+    let unknown: Vec<String> = saved
+        .iter()
+        .filter(|uuid| !known.contains(uuid))
+        .map(format_uuid)
+        .collect();
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(LoadError::UnknownComponent(unknown.join(", ")))
+    }
+}
+
+fn format_uuid(uuid: &[u8; 16]) -> String {
+    let hex: String = uuid.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Why a save file couldn't be loaded.
+#[derive(Debug)]
+pub(crate) enum LoadError {
+    NotASaveFile,
+    /// The file ends before all of its data has been read.
+    Truncated,
+    /// The file, or one of its sections, has a version this game can't read.
+    VersionMismatch {
+        what: String,
+        version: u32,
+        supported: u32,
+    },
+    /// The data is damaged.
+    Corrupt(String),
+    /// The save has component types this game doesn't know,
+    /// listed by their uuids.
+    UnknownComponent(String),
+    /// The world has no player in it.
+    MissingPlayer,
+    Io(std::io::Error),
+}
+
+pub(crate) type LoadResult<T> = std::result::Result<T, LoadError>;
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::NotASaveFile => write!(f, "Not a save file"),
+            LoadError::Truncated => write!(f, "The save file is truncated"),
+            LoadError::VersionMismatch {
+                what,
+                version,
+                supported,
+            } if version > supported => write!(
+                f,
+                "The {what} has version {version}, this game only knows {supported}"
+            ),
+            LoadError::VersionMismatch { what, version, .. } => {
+                write!(
+                    f,
+                    "The {what} has version {version}, which can't be upgraded"
+                )
+            }
+            LoadError::Corrupt(msg) => write!(f, "The save file is corrupt: {msg}"),
+            LoadError::UnknownComponent(uuids) => {
+                write!(f, "The save has components this game doesn't know: {uuids}")
+            }
+            LoadError::MissingPlayer => write!(f, "There is no player in the save"),
+            LoadError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::UnexpectedEof => LoadError::Truncated,
+            // Returned by the decompression.
+            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => {
+                LoadError::Corrupt(err.to_string())
+            }
+            _ => LoadError::Io(err),
+        }
+    }
+}
+
+impl From<bincode::Error> for LoadError {
+    fn from(err: bincode::Error) -> Self {
+        match *err {
+            bincode::ErrorKind::Io(err) => err.into(),
+            err => LoadError::Corrupt(err.to_string()),
+        }
+    }
+}
+
+fn tag_name(tag: Tag) -> String {
+    String::from_utf8_lossy(&tag).trim_end().to_string()
+}
+
+fn migrate(tag: Tag, mut version: u32, current: u32, mut data: Vec<u8>) -> LoadResult<Vec<u8>> {
+    let mismatch = |version| LoadError::VersionMismatch {
+        what: format!("section {}", tag_name(tag)),
+        version,
+        supported: current,
+    };
+    if version > current {
+        return Err(mismatch(version));
+    }
+    while version < current {
+        let migration = inventory::iter::<Migration>
+            .into_iter()
+            .find(|m| m.section == tag && m.from_version == version)
+            .ok_or_else(|| mismatch(version))?;
+        data = (migration.migrate)(data).map_err(|err| LoadError::Corrupt(err.to_string()))?;
+        version += 1;
+    }
+    Ok(data)
+}
+
+/// Summary of the player, shown when choosing what game to load.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub(crate) struct PlayerSummary {
+    pub name: String,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub turn: i64,
+    pub depth: i32,
+}
+
+/// How the sections after the header are stored.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Compression {
+    None,
+    Gzip,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct SaveHeader {
+    pub game_version: String,
+    /// When the game was saved, in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    pub player: PlayerSummary,
+    pub compression: Compression,
+}
+
+/// Version 2 added the player's depth, it was always 1 before that.
+fn header_v1_to_v2(mut data: Vec<u8>) -> Result<Vec<u8>> {
+    data.extend_from_slice(&1_i32.to_le_bytes());
+    Ok(data)
+}
+
+inventory::submit! {
+    Migration {
+        section: sections::HEADER.tag,
+        from_version: 1,
+        migrate: header_v1_to_v2,
+    }
+}
+
+/// Version 3 added compression, saves were never compressed before that.
+fn header_v2_to_v3(mut data: Vec<u8>) -> Result<Vec<u8>> {
+    // bincode writes an enum's variant index as an u32.
+    data.extend_from_slice(&0_u32.to_le_bytes());
+    Ok(data)
+}
+
+inventory::submit! {
+    Migration {
+        section: sections::HEADER.tag,
+        from_version: 2,
+        migrate: header_v2_to_v3,
+    }
+}
+
+impl SaveHeader {
+    pub(crate) fn new(player: PlayerSummary) -> Self {
+        Self {
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: timestamp_now(),
+            player,
+            compression: Compression::Gzip,
+        }
+    }
+}
+
+/// Seconds since the UNIX epoch.
+pub(crate) fn timestamp_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// The file being written, compressed after the header if asked to.
+enum Output<'a> {
+    Plain(&'a mut dyn Write),
+    Gzip(GzEncoder<&'a mut dyn Write>),
+}
+
+impl Write for Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Plain(writer) => writer.write(buf),
+            Output::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Plain(writer) => writer.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+pub(crate) struct SaveWriter<'a> {
+    writer: Output<'a>,
+}
+
+impl<'a> SaveWriter<'a> {
+    /// Starts a new save file with the given header.
+    pub(crate) fn new(writer: &'a mut dyn Write, header: &SaveHeader) -> Result<Self> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let mut save_writer = Self {
+            writer: Output::Plain(writer),
+        };
+        save_writer.write(sections::HEADER, header)?;
+        save_writer.writer = match (save_writer.writer, header.compression) {
+            (Output::Plain(writer), Compression::Gzip) => {
+                Output::Gzip(GzEncoder::new(writer, flate2::Compression::fast()))
+            }
+            (writer, _) => writer,
+        };
+        Ok(save_writer)
+    }
+
+    pub(crate) fn write<T: Serialize>(&mut self, section: Section, obj: &T) -> Result<()> {
+        let data = bincode::serialize(obj)?;
+        self.write_raw(section, &data)
+    }
+
+    pub(crate) fn write_raw(&mut self, section: Section, data: &[u8]) -> Result<()> {
+        self.write_section_header(section.tag, section.version, data)?;
+        self.writer.write_all(data)?;
+        Ok(())
+    }
+
+    fn write_section_header(&mut self, tag: Tag, version: u32, data: &[u8]) -> Result<()> {
+        self.writer.write_all(&tag)?;
+        self.writer.write_all(&version.to_le_bytes())?;
+        self.writer.write_all(&(data.len() as u64).to_le_bytes())?;
+        self.writer
+            .write_all(&crc32fast::hash(data).to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Writes the end marker.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        self.write_section_header(END_TAG, 0, &[])?;
+        match self.writer {
+            Output::Plain(writer) => writer.flush()?,
+            Output::Gzip(encoder) => encoder.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+/// The file being read, decompressed after the header if needed.
+enum Input<'a> {
+    Plain(&'a mut dyn Read),
+    Gzip(GzDecoder<&'a mut dyn Read>),
+}
+
+impl Read for Input<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Input::Plain(reader) => reader.read(buf),
+            Input::Gzip(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+pub(crate) struct SaveReader<'a> {
+    reader: Input<'a>,
+    header: SaveHeader,
+    /// Sections that have been read, but not yet asked for.
+    sections: HashMap<Tag, (u32, Vec<u8>)>,
+    at_end: bool,
+}
+
+impl<'a> SaveReader<'a> {
+    /// Checks the file's format and reads its header.
+    pub(crate) fn new(reader: &'a mut dyn Read) -> LoadResult<Self> {
+        let mut magic = [0_u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(LoadError::NotASaveFile);
+        }
+        let format_version = read_u32(reader)?;
+        if format_version > FORMAT_VERSION {
+            return Err(LoadError::VersionMismatch {
+                what: "save file format".to_string(),
+                version: format_version,
+                supported: FORMAT_VERSION,
+            });
+        }
+
+        let mut save_reader = Self {
+            reader: Input::Plain(reader),
+            header: SaveHeader::new(PlayerSummary::default()),
+            sections: HashMap::new(),
+            at_end: false,
+        };
+        // Only the header is read here, so the rest can still be decompressed.
+        save_reader.header = save_reader.read(sections::HEADER)?;
+        save_reader.reader = match (save_reader.reader, save_reader.header.compression) {
+            (Input::Plain(reader), Compression::Gzip) => Input::Gzip(GzDecoder::new(reader)),
+            (reader, _) => reader,
+        };
+        Ok(save_reader)
+    }
+
+    pub(crate) fn header(&self) -> &SaveHeader {
+        &self.header
+    }
+
+    pub(crate) fn read<T: DeserializeOwned>(&mut self, section: Section) -> LoadResult<T> {
+        let data = self.read_raw(section)?;
+        Ok(bincode::deserialize(&data)?)
+    }
+
+    /// Like `read`, for sections that older saves don't have.
+    pub(crate) fn read_optional<T: DeserializeOwned>(
+        &mut self,
+        section: Section,
+    ) -> LoadResult<Option<T>> {
+        if !self.has_section(section)? {
+            return Ok(None);
+        }
+        self.read(section).map(Some)
+    }
+
+    fn has_section(&mut self, section: Section) -> LoadResult<bool> {
+        while !self.sections.contains_key(&section.tag) && !self.at_end {
+            self.read_next_section()?;
+        }
+        Ok(self.sections.contains_key(&section.tag))
+    }
+
+    /// Returns the section's data, upgraded to the section's current version.
+    pub(crate) fn read_raw(&mut self, section: Section) -> LoadResult<Vec<u8>> {
+        self.has_section(section)?;
+        let (version, data) = self.sections.remove(&section.tag).ok_or_else(|| {
+            LoadError::Corrupt(format!("section {} is missing", tag_name(section.tag)))
+        })?;
+        migrate(section.tag, version, section.version, data)
+    }
+
+    fn read_next_section(&mut self) -> LoadResult<()> {
+        let mut tag = [0_u8; 4];
+        self.reader.read_exact(&mut tag)?;
+        let version = read_u32(&mut self.reader)?;
+        let mut len = [0_u8; 8];
+        self.reader.read_exact(&mut len)?;
+        let len = u64::from_le_bytes(len);
+        let crc = read_u32(&mut self.reader)?;
+
+        if tag == END_TAG {
+            self.at_end = true;
+            return Ok(());
+        }
+
+        let mut data = vec![];
+        (&mut self.reader).take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(LoadError::Truncated);
+        }
+        if crc32fast::hash(&data) != crc {
+            return Err(LoadError::Corrupt(format!(
+                "section {} has the wrong CRC",
+                tag_name(tag)
+            )));
+        }
+        self.sections.insert(tag, (version, data));
+        Ok(())
+    }
+}
+
+fn read_u32(reader: &mut dyn Read) -> LoadResult<u32> {
+    let mut data = [0_u8; 4];
+    reader.read_exact(&mut data)?;
+    Ok(u32::from_le_bytes(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST: Section = Section {
+        tag: *b"TEST",
+        version: 1,
+    };
+
+    fn save_test_file(compression: Compression) -> Vec<u8> {
+        let mut data = vec![];
+        let mut header = SaveHeader::new(PlayerSummary {
+            name: "Kim".to_string(),
+            hp: 10,
+            max_hp: 20,
+            turn: 1234,
+            depth: 3,
+        });
+        header.compression = compression;
+        let mut writer = SaveWriter::new(&mut data, &header).unwrap();
+        writer.write(TEST, &"Hello".to_string()).unwrap();
+        writer.finish().unwrap();
+        data
+    }
+
+    #[test]
+    fn read_saved_sections() {
+        for compression in [Compression::None, Compression::Gzip] {
+            let data = save_test_file(compression);
+            let mut slice = &data[..];
+            let mut reader = SaveReader::new(&mut slice).unwrap();
+            assert_eq!(reader.header().player.name, "Kim");
+            assert_eq!(reader.header().player.turn, 1234);
+            assert_eq!(reader.header().compression, compression);
+            assert_eq!(reader.read::<String>(TEST).unwrap(), "Hello");
+            assert!(reader.read::<String>(sections::MAP).is_err());
+            assert!(
+                reader
+                    .read_optional::<String>(sections::MAP)
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn detect_corrupt_files() {
+        let mut data = save_test_file(Compression::None);
+        let mut slice = &data[..4];
+        assert!(matches!(
+            SaveReader::new(&mut slice),
+            Err(LoadError::Truncated)
+        ));
+
+        let mut slice = &data[..data.len() - 22];
+        let mut reader = SaveReader::new(&mut slice).unwrap();
+        assert!(matches!(
+            reader.read::<String>(TEST),
+            Err(LoadError::Truncated)
+        ));
+
+        let last = data.len() - 21;
+        data[last] ^= 0xff;
+        let mut slice = &data[..];
+        let mut reader = SaveReader::new(&mut slice).unwrap();
+        assert!(matches!(
+            reader.read::<String>(TEST),
+            Err(LoadError::Corrupt(_))
+        ));
+
+        data[0] = b'X';
+        let mut slice = &data[..];
+        assert!(matches!(
+            SaveReader::new(&mut slice),
+            Err(LoadError::NotASaveFile)
+        ));
+    }
+
+    #[test]
+    fn detect_truncated_compressed_files() {
+        let data = save_test_file(Compression::Gzip);
+        // The test section and the end marker follow the header.
+        let tail_len = 20 + 13 + 20;
+        let header_len = save_test_file(Compression::None).len() - tail_len;
+        let mut slice = &data[..header_len + 20];
+        let mut reader = SaveReader::new(&mut slice).unwrap();
+        assert!(reader.read::<String>(TEST).is_err());
+    }
+
+    #[test]
+    fn migrate_header_from_v1() {
+        let old_header = (
+            "0.1.0".to_string(),
+            1_u64,
+            ("Kim".to_string(), 10_i32, 20_i32, 1234_i64),
+        );
+        let data = bincode::serialize(&old_header).unwrap();
+        let data = migrate(sections::HEADER.tag, 1, sections::HEADER.version, data).unwrap();
+        let header: SaveHeader = bincode::deserialize(&data).unwrap();
+        assert_eq!(header.player.name, "Kim");
+        assert_eq!(header.player.turn, 1234);
+        assert_eq!(header.player.depth, 1);
+        assert_eq!(header.compression, Compression::None);
+    }
+
+    #[test]
+    fn newer_sections_are_rejected() {
+        let data = save_test_file(Compression::Gzip);
+        let mut slice = &data[..];
+        let mut reader = SaveReader::new(&mut slice).unwrap();
+        let old_test = Section {
+            tag: TEST.tag,
+            version: 0,
+        };
+        assert!(matches!(
+            reader.read::<String>(old_test),
+            Err(LoadError::VersionMismatch { version: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn unknown_components_are_rejected() {
+        assert!(check_components(&known_components()).is_ok());
+        let mut uuid = [0_u8; 16];
+        uuid[0] = 0xab;
+        uuid[15] = 0x01;
+        match check_components(&[uuid]) {
+            Err(LoadError::UnknownComponent(uuids)) => {
+                assert_eq!(uuids, "ab000000-0000-0000-0000-000000000001")
+            }
+            _ => panic!("the component should be unknown"),
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Named save slots, stored as files in the user's data directory.
+
+use crate::save_file::{SaveHeader, SaveReader};
+use ::std::fs::File;
+use ::std::io::{BufReader, BufWriter, Write};
+use ::std::path::{Path, PathBuf};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + 'static + Send + Sync>>;
+
+const SAVE_EXTENSION: &str = "sav";
+
+/// The slot the game is saved to every now and then, in case it crashes.
+pub(crate) const AUTOSAVE_SLOT: &str = "Autosave";
+
+/// Holds the time the game last exited normally.
+const CLEAN_EXIT_FILE: &str = "clean_exit";
+
+/// The slot the current game was loaded from or last saved to.
+pub(crate) struct CurrentSlot(pub String);
+
+pub(crate) struct SaveSlot {
+    pub name: String,
+    pub path: PathBuf,
+    /// The save's header, or why it couldn't be read.
+    pub header: std::result::Result<SaveHeader, String>,
+}
+
+impl SaveSlot {
+    /// One line description of the save.
+    pub(crate) fn describe(&self) -> String {
+        match &self.header {
+            Ok(header) => format!(
+                "{:16} {:12} Depth {:<3} Turn {:<7} {}",
+                self.name,
+                header.player.name,
+                header.player.depth,
+                header.player.turn,
+                format_timestamp(header.timestamp)
+            ),
+            Err(err) => format!("{:16} {}", self.name, err),
+        }
+    }
+}
+
+pub(crate) fn save_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("rouge").join("saves"))
+        .unwrap_or_else(|| PathBuf::from("saves"))
+}
+
+pub(crate) fn slot_path(name: &str) -> PathBuf {
+    save_dir().join(format!("{name}.{SAVE_EXTENSION}"))
+}
+
+/// Removes characters that don't belong in file names.
+pub(crate) fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Returns all slots, the most recently saved first.
+pub(crate) fn list_slots() -> Vec<SaveSlot> {
+    let mut slots: Vec<_> = std::fs::read_dir(save_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .filter_map(read_slot)
+        .collect();
+    slots.sort_by_key(|slot| {
+        std::cmp::Reverse(slot.header.as_ref().map_or(0, |header| header.timestamp))
+    });
+    slots
+}
+
+fn read_slot(path: PathBuf) -> Option<SaveSlot> {
+    let name = path.file_stem()?.to_string_lossy().to_string();
+    let header = read_header(&path).map_err(|err| err.to_string());
+    Some(SaveSlot { name, path, header })
+}
+
+fn read_header(path: &PathBuf) -> Result<SaveHeader> {
+    let mut file = BufReader::new(File::open(path)?);
+    let reader = SaveReader::new(&mut file)?;
+    Ok(reader.header().clone())
+}
+
+/// Returns a slot name that isn't used yet, ie "Game 3".
+pub(crate) fn unused_slot_name() -> String {
+    (1..)
+        .map(|i| format!("Game {i}"))
+        .find(|name| !slot_path(name).exists())
+        .expect("Some name to be free")
+}
+
+/// Writes the slot's save file, and creates its directory if needed.
+///
+/// The save is written to a temporary file first, which then replaces the
+/// old save, so the old save is kept if the game crashes while saving.
+pub(crate) fn write_slot(
+    name: &str,
+    save: impl FnOnce(&mut dyn Write) -> crate::Result<()>,
+) -> Result<()> {
+    std::fs::create_dir_all(save_dir())?;
+    let path = slot_path(name);
+    let tmp_path = path.with_extension("tmp");
+
+    if let Err(err) = write_file(&tmp_path, save) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+fn write_file(path: &Path, save: impl FnOnce(&mut dyn Write) -> crate::Result<()>) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    save(&mut writer)?;
+    // Make sure the data is on the disk before it replaces the old save.
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+    Ok(())
+}
+
+pub(crate) fn delete_slot(name: &str) -> Result<()> {
+    std::fs::remove_file(slot_path(name))?;
+    Ok(())
+}
+
+pub(crate) fn rename_slot(name: &str, new_name: &str) -> Result<()> {
+    let new_path = slot_path(new_name);
+    if new_path.exists() {
+        return Err(format!("There is already a save called {new_name}").into());
+    }
+    std::fs::rename(slot_path(name), new_path)?;
+    Ok(())
+}
+
+/// Remembers that the game exited normally.
+pub(crate) fn mark_clean_exit() -> Result<()> {
+    std::fs::create_dir_all(save_dir())?;
+    std::fs::write(
+        save_dir().join(CLEAN_EXIT_FILE),
+        crate::save_file::timestamp_now().to_string(),
+    )?;
+    Ok(())
+}
+
+/// Marks a clean exit, printing why it failed to stderr, as the game is
+/// on its way out.
+pub(crate) fn mark_clean_exit_or_complain() {
+    if let Err(err) = mark_clean_exit() {
+        eprintln!("Failed to mark the exit as clean: {err}");
+    }
+}
+
+/// Returns the autosave's name and header if it was written after the game
+/// last exited normally, ie the game probably crashed.
+pub(crate) fn autosave_to_recover() -> Option<(String, SaveHeader)> {
+    let slot = read_slot(slot_path(AUTOSAVE_SLOT))?;
+    let header = slot.header.ok()?;
+    let clean_exit = std::fs::read_to_string(save_dir().join(CLEAN_EXIT_FILE))
+        .ok()
+        .and_then(|timestamp| timestamp.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    (header.timestamp > clean_exit).then_some((slot.name, header))
+}
+
+/// Renames a save that can't be loaded, so it is no longer listed,
+/// but is still there to be looked at. Returns its new path.
+pub(crate) fn move_aside(name: &str) -> Result<PathBuf> {
+    let new_path = std::iter::once(format!("{name}.bad"))
+        .chain((2..).map(|i| format!("{name} {i}.bad")))
+        .map(|file_name| save_dir().join(file_name))
+        .find(|path| !path.exists())
+        .expect("Some name to be free");
+    std::fs::rename(slot_path(name), &new_path)?;
+    Ok(new_path)
+}
+
+/// Formats seconds since the UNIX epoch as "YYYY-MM-DD HH:MM" (UTC).
+pub(crate) fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let minutes = (timestamp % 86400) / 60;
+
+    // Convert days to a civil date, from Howard Hinnant's date algorithms.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(1_792_309_180), "2026-10-18 07:39");
+    }
+
+    #[test]
+    fn sanitize_names() {
+        assert_eq!(sanitize_name(" My ../game: 2 "), "My game 2");
+    }
+}
@@ -0,0 +1,182 @@
+use super::{Scene, SceneResult};
+use crate::State;
+use crate::components::*;
+use crate::ecs::Ecs;
+use crate::resources::PlayerEntity;
+use ::bracket_lib::prelude::*;
+use ::legion::*;
+
+/// Spends the attribute points the player gained by levelling up.
+pub(crate) struct LevelUpScene {}
+
+impl Scene<State> for LevelUpScene {
+    fn tick(&mut self, gs: &mut State, ctx: &mut BTerm) -> SceneResult<State> {
+        let player_entity = resource_get!(gs.ecs, PlayerEntity).0;
+        let Some(mut entry) = gs.ecs.world.entry(player_entity) else {
+            return SceneResult::Pop;
+        };
+        let mut experience = entry
+            .get_component::<Experience>()
+            .cloned()
+            .unwrap_or_default();
+        if experience.unspent_points < 1 {
+            return SceneResult::Pop;
+        }
+        let mut attributes = entry
+            .get_component::<Attributes>()
+            .cloned()
+            .unwrap_or_default();
+
+        let lines = [
+            format!(
+                "You reached level {}, raise an attribute ({} left):",
+                experience.level, experience.unspent_points
+            ),
+            String::new(),
+            format!(
+                "a) Strength      {:2}  {:+} to hit and damage",
+                attributes.strength,
+                Attributes::bonus(attributes.strength)
+            ),
+            format!(
+                "b) Dexterity     {:2}  {:+} defense",
+                attributes.dexterity,
+                Attributes::bonus(attributes.dexterity)
+            ),
+            format!(
+                "c) Constitution  {:2}  {:+} hp per level",
+                attributes.constitution,
+                Attributes::bonus(attributes.constitution)
+            ),
+        ];
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        crate::gui::show_message(ctx, "Level up", &lines);
+
+        let attribute = match ctx
+            .key
+            .map(|key| crate::gui::letter_to_option(ctx.shift, key))
+        {
+            Some(0) => &mut attributes.strength,
+            Some(1) => &mut attributes.dexterity,
+            Some(2) => &mut attributes.constitution,
+            _ => return SceneResult::Continue,
+        };
+        *attribute += 1;
+        experience.unspent_points -= 1;
+        entry.add_component(attributes);
+        entry.add_component(experience);
+        SceneResult::Continue
+    }
+}
+
+impl LevelUpScene {
+    pub(crate) fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Shows the player's stats, where their bonuses come from, and their
+/// progress to the next level.
+pub(crate) struct CharacterSheetScene {
+    lines: Vec<String>,
+}
+
+impl Scene<State> for CharacterSheetScene {
+    fn tick(&mut self, _: &mut State, ctx: &mut BTerm) -> SceneResult<State> {
+        let lines: Vec<&str> = self.lines.iter().map(String::as_str).collect();
+        crate::gui::show_message(ctx, "Character", &lines);
+        match ctx.key {
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::Escape) => SceneResult::Pop,
+            _ => SceneResult::Continue,
+        }
+    }
+}
+
+impl CharacterSheetScene {
+    pub(crate) fn new(ecs: &Ecs) -> Self {
+        let player_entity = resource_get!(ecs, PlayerEntity).0;
+        let Ok(entry) = ecs.world.entry_ref(player_entity) else {
+            return Self { lines: vec![] };
+        };
+        let stats = entry.get_component::<CombatStats>().unwrap();
+        let experience = entry
+            .get_component::<Experience>()
+            .cloned()
+            .unwrap_or_default();
+        let attributes = entry
+            .get_component::<Attributes>()
+            .cloned()
+            .unwrap_or_default();
+        let strength = Attributes::bonus(attributes.strength);
+        let dexterity = Attributes::bonus(attributes.dexterity);
+        let constitution = Attributes::bonus(attributes.constitution);
+
+        let mut power_bonus = 0;
+        let mut defense_bonus = 0;
+        let mut melee_damage = entry
+            .get_component::<MeleeDamage>()
+            .cloned()
+            .unwrap_or(MeleeDamage::UNARMED);
+        let mut query = <(
+            &Equipped,
+            Option<&MeleePowerBonus>,
+            Option<&DefenseBonus>,
+            Option<&MeleeDamage>,
+        )>::query();
+        for (equipped, power, defense, damage) in query.iter(&ecs.world) {
+            if equipped.owner != player_entity {
+                continue;
+            }
+            power_bonus += power.map_or(0, |bonus| bonus.power);
+            defense_bonus += defense.map_or(0, |bonus| bonus.defense);
+            if let Some(damage) = damage {
+                melee_damage = damage.clone();
+            }
+        }
+
+        let lines = vec![
+            format!(
+                "Level {}, {} / {} xp",
+                experience.level,
+                experience.xp,
+                experience.next_level_xp()
+            ),
+            format!("HP {} / {}", stats.hp, stats.max_hp),
+            String::new(),
+            format!(
+                "Strength      {:2}  {strength:+} to hit and damage",
+                attributes.strength
+            ),
+            format!(
+                "Dexterity     {:2}  {dexterity:+} defense",
+                attributes.dexterity
+            ),
+            format!(
+                "Constitution  {:2}  {constitution:+} hp per level",
+                attributes.constitution
+            ),
+            String::new(),
+            format!(
+                "Power    {:2}  ({}, {power_bonus:+} equipment, {strength:+} strength)",
+                stats.power + power_bonus + strength,
+                stats.power
+            ),
+            format!(
+                "Defense  {:2}  ({}, {defense_bonus:+} equipment, {dexterity:+} dexterity)",
+                stats.defense + defense_bonus + dexterity,
+                stats.defense
+            ),
+            format!(
+                "Damage   {} {}",
+                Dice {
+                    bonus: melee_damage.dice.bonus + strength,
+                    ..melee_damage.dice
+                },
+                melee_damage.damage_type.name()
+            ),
+            String::new(),
+            "Press ENTER".to_string(),
+        ];
+        Self { lines }
+    }
+}
@@ -0,0 +1,52 @@
+use super::{Scene, SceneResult};
+use crate::messages::WantsToDropMessage;
+use crate::queues::WantsToDropQueue;
+use crate::resources::PlayerEntity;
+use crate::{RunState, State};
+use ::bracket_lib::prelude::*;
+use ::legion::Entity;
+
+/// Asks how many of a stack to drop.
+pub(crate) struct DropCountScene {
+    item: Entity,
+    max: i32,
+    text: String,
+}
+
+impl Scene<State> for DropCountScene {
+    fn tick(&mut self, gs: &mut State, ctx: &mut BTerm) -> SceneResult<State> {
+        let hint = format!("Drop how many (1-{})? All of them if empty.", self.max);
+        match crate::gui::ask_text(ctx, "Drop", &hint, "Count:", &mut self.text, 4) {
+            crate::gui::ItemMenuResult::Selected => {
+                let count = match self.text.trim().parse::<i32>() {
+                    _ if self.text.trim().is_empty() => None,
+                    Ok(count) if (1..=self.max).contains(&count) => Some(count),
+                    _ => {
+                        self.text.clear();
+                        return SceneResult::Continue;
+                    }
+                };
+                let player_entity = resource_get!(gs.ecs, PlayerEntity).0;
+                resource_get!(gs.ecs, WantsToDropQueue).send(WantsToDropMessage {
+                    who: player_entity,
+                    item: self.item,
+                    count,
+                });
+                gs.ecs.resources.insert(RunState::Tick);
+                SceneResult::Pop
+            }
+            crate::gui::ItemMenuResult::Cancel => SceneResult::Pop,
+            crate::gui::ItemMenuResult::NoResponse => SceneResult::Continue,
+        }
+    }
+}
+
+impl DropCountScene {
+    pub(crate) fn new(item: Entity, max: i32) -> Self {
+        Self {
+            item,
+            max,
+            text: String::new(),
+        }
+    }
+}
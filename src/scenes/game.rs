@@ -1,18 +1,26 @@
+use crate::State;
 use crate::components::*;
 use crate::ecs::Ecs;
-use crate::messages::{WantsToDropMessage, WantsToUseMessage};
+use crate::messages::{
+    WantsToDropMessage, WantsToEquipMessage, WantsToThrowMessage, WantsToUnequipMessage,
+    WantsToUseMessage,
+};
 use crate::player::player_input;
-use crate::queues::{WantsToDropQueue, WantsToUseQueue};
-use crate::resources::{Camera, Map, PlayerEntity, PlayerPosition};
-use crate::State;
-use crate::{gui, RunState};
+use crate::queues::{
+    WantsToDropQueue, WantsToEquipQueue, WantsToThrowQueue, WantsToUnequipQueue, WantsToUseQueue,
+};
+use crate::resources::{Camera, GameLog, Map, PlayerEntity, PlayerPosition, Time};
+use crate::{RunState, TargetAction, gui};
 use ::bracket_lib::prelude::*;
+use ::legion::query::component;
 use ::legion::*;
 
 use super::{Scene, SceneResult};
 
 pub(crate) struct GameScene {
     schedule: Schedule,
+    /// Runs instead of the turns while an animation is blocking them.
+    animations: Schedule,
 }
 
 impl Scene<State> for GameScene {
@@ -36,13 +44,49 @@ impl Scene<State> for GameScene {
 
         let oldrunstate = { *resource_get!(ecs, RunState) };
         let mut newrunstate = oldrunstate;
+        let mut result = SceneResult::Continue;
 
         match newrunstate {
             RunState::SaveGame => {
-                // TODO: Implement save
-                // return SceneResult::Push(Box::new(crate::scenes::SaveScene::new()));
+                result = SceneResult::Push(Box::new(super::SaveGameScene::new(ecs)));
+                newrunstate = RunState::AwaitingInput;
+            }
+            RunState::CharacterSheet => {
+                result =
+                    SceneResult::Push(Box::new(super::character::CharacterSheetScene::new(ecs)));
                 newrunstate = RunState::AwaitingInput;
             }
+            RunState::PickUp => {
+                result = SceneResult::Push(Box::new(super::pick_up::PickUpScene::new(ecs)));
+                newrunstate = RunState::AwaitingInput;
+            }
+            RunState::ChangeLevel(delta) => {
+                if let Err(err) = crate::dungeon::change_level(ecs, &gs.registry, delta) {
+                    let mut gamelog = resource_get_mut!(ecs, GameLog);
+                    gamelog.set_color(RED);
+                    gamelog.write_text(format!("Failed to change level: {err}"));
+                    gamelog.end_of_line();
+                }
+                newrunstate = RunState::Tick;
+            }
+            RunState::CloseDoor => {
+                ctx.print_color(
+                    5,
+                    0,
+                    RGB::named(YELLOW),
+                    RGB::named(BLACK),
+                    "Close the door in which direction?",
+                );
+                match ctx.key {
+                    Some(VirtualKeyCode::Escape) => newrunstate = RunState::AwaitingInput,
+                    Some(key) => {
+                        if let Some(dir) = gui::key_to_dir(key) {
+                            newrunstate = crate::player::close_door_at(ecs, dir);
+                        }
+                    }
+                    None => (),
+                }
+            }
             RunState::PreRun => {
                 self.run_systems(ecs);
                 newrunstate = RunState::AwaitingInput;
@@ -51,6 +95,7 @@ impl Scene<State> for GameScene {
                 newrunstate = player_input(ecs, ctx);
                 let mut schedule = legion::Schedule::builder()
                     .add_system(crate::systems::output_system())
+                    .add_system(crate::systems::delete_after_time_system())
                     .build();
                 schedule.execute(&mut ecs.world, &mut ecs.resources);
             }
@@ -61,6 +106,15 @@ impl Scene<State> for GameScene {
                 (gui::ItemMenuResult::Selected, true) => ctx.quit(),
                 _ => (),
             },
+            RunState::Animating => {
+                self.animations.execute(&mut ecs.world, &mut ecs.resources);
+                if !Self::animating(ecs) {
+                    newrunstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::EnergylessTick | RunState::Tick if Self::animating(ecs) => {
+                self.animations.execute(&mut ecs.world, &mut ecs.resources);
+            }
             RunState::EnergylessTick | RunState::Tick => {
                 self.run_systems(ecs);
                 let entity = resource_get!(ecs, PlayerEntity).0;
@@ -69,11 +123,19 @@ impl Scene<State> for GameScene {
                     .entry(entity)
                     .map_or(RunState::ReallyQuit, |entry| {
                         if entry.get_component::<Energy>().unwrap().energy >= 0 {
-                            RunState::AwaitingInput
+                            RunState::Animating
                         } else {
                             RunState::Tick
                         }
                     });
+                let level_up = ecs.world.entry(entity).is_some_and(|entry| {
+                    entry
+                        .get_component::<Experience>()
+                        .is_ok_and(|experience| experience.unspent_points > 0)
+                });
+                if level_up {
+                    result = SceneResult::Push(Box::new(super::character::LevelUpScene::new()));
+                }
             }
             RunState::ShowInventory(inv_type) => match gui::show_inventory(ecs, ctx, inv_type) {
                 (gui::ItemMenuResult::Cancel, _) => newrunstate = RunState::AwaitingInput,
@@ -83,14 +145,33 @@ impl Scene<State> for GameScene {
                         crate::InventoryType::Apply => {
                             let should_add_wants_to_use = {
                                 let entry = ecs.world.entry(item_entity).unwrap();
-                                if let Ok(range) = entry.get_component::<Ranged>() {
+                                if entry.get_component::<Equippable>().is_ok() {
+                                    // Using equipment is wielding or wearing it.
+                                    resource_get!(ecs, WantsToEquipQueue).send(
+                                        WantsToEquipMessage {
+                                            who: player_entity,
+                                            item: item_entity,
+                                        },
+                                    );
+                                    newrunstate = RunState::Tick;
+                                    false
+                                } else if let Ok(range) = entry.get_component::<Ranged>() {
                                     let player_position = resource_get!(ecs, PlayerPosition).0;
                                     let camera = resource_get!(ecs, Camera);
                                     let start_pos = camera.transform_map_pos(player_position);
+                                    let area =
+                                        entry.get_component::<AreaOfEffect>().ok().map(|area| {
+                                            let shape =
+                                                entry.get_component::<AreaShape>().ok().copied();
+                                            (*area, shape.unwrap_or_default())
+                                        });
                                     let targeting_info =
-                                        gui::TargetingInfo::new(range.range, start_pos, ctx);
-                                    newrunstate =
-                                        RunState::ShowTargeting(targeting_info, item_entity);
+                                        gui::TargetingInfo::new(range.range, start_pos, ctx)
+                                            .with_area(area);
+                                    newrunstate = RunState::ShowTargeting(
+                                        targeting_info,
+                                        TargetAction::Use(item_entity),
+                                    );
                                     false
                                 } else {
                                     true
@@ -107,27 +188,91 @@ impl Scene<State> for GameScene {
                             }
                         }
                         crate::InventoryType::Drop => {
-                            resource_get!(ecs, WantsToDropQueue).send(WantsToDropMessage {
+                            let count = ecs.world.entry_ref(item_entity).map_or(1, |entry| {
+                                entry
+                                    .get_component::<Quantity>()
+                                    .map_or(1, |quantity| quantity.count)
+                            });
+                            if count > 1 {
+                                result = SceneResult::Push(Box::new(
+                                    super::drop_count::DropCountScene::new(item_entity, count),
+                                ));
+                                newrunstate = RunState::AwaitingInput;
+                            } else {
+                                resource_get!(ecs, WantsToDropQueue).send(WantsToDropMessage {
+                                    who: player_entity,
+                                    item: item_entity,
+                                    count: None,
+                                });
+                                newrunstate = RunState::Tick;
+                            }
+                        }
+                        crate::InventoryType::Wield | crate::InventoryType::Wear => {
+                            resource_get!(ecs, WantsToEquipQueue).send(WantsToEquipMessage {
+                                who: player_entity,
+                                item: item_entity,
+                            });
+                            newrunstate = RunState::Tick;
+                        }
+                        crate::InventoryType::Remove => {
+                            resource_get!(ecs, WantsToUnequipQueue).send(WantsToUnequipMessage {
                                 who: player_entity,
                                 item: item_entity,
                             });
                             newrunstate = RunState::Tick;
                         }
+                        crate::InventoryType::Throw => {
+                            let player_position = resource_get!(ecs, PlayerPosition).0;
+                            let start_pos =
+                                resource_get!(ecs, Camera).transform_map_pos(player_position);
+                            let targeting_info = gui::TargetingInfo::new(
+                                crate::systems::THROW_RANGE,
+                                start_pos,
+                                ctx,
+                            );
+                            newrunstate = RunState::ShowTargeting(
+                                targeting_info,
+                                TargetAction::Throw(item_entity),
+                            );
+                        }
                     }
                 }
                 _ => (),
             },
-            RunState::ShowTargeting(ref mut targeting_info, item_entity) => {
+            RunState::ShowTargeting(ref mut targeting_info, action) => {
                 match targeting_info.show_targeting(ecs, ctx) {
                     (gui::ItemMenuResult::Cancel, _) => newrunstate = RunState::AwaitingInput,
                     (gui::ItemMenuResult::Selected, Some(target_position)) => {
                         let player_entity = resource_get!(ecs, PlayerEntity).0;
 
-                        resource_get!(ecs, WantsToUseQueue).send(WantsToUseMessage {
-                            who: player_entity,
-                            item: item_entity,
-                            target: Some(target_position),
-                        });
+                        match action {
+                            TargetAction::Use(item) => {
+                                resource_get!(ecs, WantsToUseQueue).send(WantsToUseMessage {
+                                    who: player_entity,
+                                    item,
+                                    target: Some(target_position),
+                                });
+                            }
+                            TargetAction::Throw(item) => {
+                                resource_get!(ecs, WantsToThrowQueue).send(WantsToThrowMessage {
+                                    who: player_entity,
+                                    item,
+                                    target: target_position,
+                                    launcher: None,
+                                });
+                            }
+                            TargetAction::Fire {
+                                launcher,
+                                ammunition,
+                            } => {
+                                resource_get!(ecs, WantsToThrowQueue).send(WantsToThrowMessage {
+                                    who: player_entity,
+                                    item: ammunition,
+                                    target: target_position,
+                                    launcher: Some(launcher),
+                                });
+                            }
+                        }
 
                         newrunstate = RunState::Tick;
                     }
@@ -140,43 +285,74 @@ impl Scene<State> for GameScene {
 
         ecs.resources.insert(newrunstate);
 
-        SceneResult::Continue
+        if newrunstate == RunState::AwaitingInput {
+            super::save_game::autosave(gs);
+        }
+
+        result
     }
 }
 
 impl GameScene {
+    /// Starts a new game.
     pub(crate) fn new(gs: &mut State) -> Self {
+        Self::with_run_state(gs, RunState::PreRun)
+    }
+
+    /// Continues a loaded game where it was saved.
+    pub(crate) fn resume(gs: &mut State) -> Self {
+        Self::with_run_state(gs, RunState::AwaitingInput)
+    }
+
+    fn with_run_state(gs: &mut State, run_state: RunState) -> Self {
         let ecs = &mut gs.ecs;
 
-        ecs.resources.insert(RunState::PreRun);
+        ecs.resources.insert(run_state);
         let mut builder = Schedule::builder();
         builder
             .add_system(crate::systems::regain_energy_system())
             .add_system(crate::systems::monster_ai_system())
             .add_system(crate::systems::melee_combat_system())
+            .add_system(crate::systems::missiles_system())
             .add_system(crate::systems::drop_system())
-            .add_system(crate::systems::pickup_system());
+            .add_system(crate::systems::pickup_system())
+            .add_system(crate::systems::equip_system())
+            .add_system(crate::systems::unequip_system())
+            .add_system(crate::systems::trap_system());
         crate::systems::add_viewshed_system(ecs, &mut builder);
         builder
+            .add_system(crate::systems::perception_system())
             .flush()
             .add_system(crate::systems::consume_system())
             .flush()
+            .add_system(crate::systems::status_effects_system())
+            .add_system(crate::systems::hunger_system())
+            .add_system(crate::systems::natural_regeneration_system())
+            .flush()
             .add_system(crate::systems::damage_system())
             .add_system(crate::systems::health_system())
             .flush()
             .add_system(crate::systems::output_die_system())
             .flush()
             .add_system(crate::systems::output_system())
+            .add_system(crate::systems::particles_system())
             .flush()
             .add_system(crate::systems::delete_the_dead_system())
+            .add_system(crate::systems::drop_equipment_of_the_dead_system())
             .add_system(crate::systems::delete_items_system())
             .add_system(crate::systems::delete_after_time_system())
             .add_system(crate::systems::delete_after_tick_system())
             .flush()
+            .add_system(crate::systems::experience_system())
             .add_system(crate::systems::map_indexing_clear_system())
-            .add_system(crate::systems::map_indexing_system());
+            .add_system(crate::systems::map_indexing_system())
+            .add_system(crate::systems::map_indexing_traps_system());
         Self {
             schedule: builder.build(),
+            animations: Schedule::builder()
+                .add_system(crate::systems::delete_after_time_system())
+                .flush()
+                .build(),
         }
     }
 
@@ -184,11 +360,16 @@ impl GameScene {
         self.schedule.execute(&mut ecs.world, &mut ecs.resources);
     }
 
+    fn animating(ecs: &Ecs) -> bool {
+        crate::systems::animating(&ecs.world, &resource_get!(ecs, Time))
+    }
+
     fn draw_map(&mut self, ecs: &mut Ecs, ctx: &mut BTerm) {
         let player_position = resource_get!(ecs, PlayerPosition);
         {
             let mut camera = resource_get_mut!(ecs, Camera);
-            crate::systems::camera_update(&mut camera, &player_position);
+            let map = resource_get!(ecs, Map);
+            crate::systems::camera_update(&mut camera, &player_position, &map);
         }
 
         crate::resources::draw_map(ecs, ctx);
@@ -204,6 +385,7 @@ impl GameScene {
         ctx.set_active_console(crate::LAYERS);
 
         let mut data = <(&Position, &Renderable)>::query()
+            .filter(!component::<Hidden>() & !component::<Particle>())
             .iter(&ecs.world)
             .filter(|(p, _)| camera.is_in_view(p.0))
             .collect::<Vec<_>>();
@@ -216,5 +398,18 @@ impl GameScene {
                 ctx.set(point.x, point.y, render.fg, render.bg, render.glyph);
             }
         }
+
+        let now = resource_get!(ecs, Time).real_time_ms;
+        for (pos, render, particle) in
+            <(&Position, &Renderable, &Particle)>::query().iter(&ecs.world)
+        {
+            if particle.start_time_ms <= now
+                && camera.is_in_view(pos.0)
+                && map.visible_tiles[map.pos_to_idx(*pos)]
+            {
+                let point = camera.transform_map_pos(pos.0);
+                ctx.set(point.x, point.y, render.fg, render.bg, render.glyph);
+            }
+        }
     }
 }
@@ -0,0 +1,116 @@
+use super::slot_browser::{SlotBrowser, SlotBrowserResult};
+use super::{Scene, SceneResult};
+use crate::ecs::Ecs;
+use crate::resources::Time;
+use crate::save_file::{LoadError, LoadResult};
+use crate::save_slots::{self, CurrentSlot};
+use crate::State;
+use ::bracket_lib::prelude::*;
+use ::std::fs::File;
+use ::std::io::BufReader;
+
+/// A save that couldn't be loaded.
+struct Failed {
+    name: String,
+    error: LoadError,
+}
+
+/// Lets the player choose a saved game to continue.
+pub(crate) struct LoadGameScene {
+    browser: SlotBrowser,
+    failed: Option<Failed>,
+    /// A slot to load right away, without asking.
+    load_now: Option<String>,
+}
+
+impl Scene<State> for LoadGameScene {
+    fn tick(&mut self, gs: &mut State, ctx: &mut BTerm) -> SceneResult<State> {
+        ctx.cls();
+
+        if let Some(name) = self.load_now.take() {
+            return self.load(gs, name);
+        }
+        if self.failed.is_some() {
+            self.ask_move_aside(ctx);
+            return SceneResult::Continue;
+        }
+
+        match self.browser.tick(ctx) {
+            SlotBrowserResult::Cancel => SceneResult::Pop,
+            SlotBrowserResult::Selected(name) => self.load(gs, name),
+            SlotBrowserResult::New(_) | SlotBrowserResult::Continue => SceneResult::Continue,
+        }
+    }
+}
+
+impl LoadGameScene {
+    pub(crate) fn new() -> Self {
+        Self {
+            browser: Self::browser(),
+            failed: None,
+            load_now: None,
+        }
+    }
+
+    /// Loads the given slot, and only shows the saves if that fails.
+    pub(crate) fn resume(name: String) -> Self {
+        Self {
+            load_now: Some(name),
+            ..Self::new()
+        }
+    }
+
+    fn load(&mut self, gs: &mut State, name: String) -> SceneResult<State> {
+        match load_slot(gs, &name) {
+            Ok(()) => SceneResult::ReplaceAll(Box::new(super::game::GameScene::resume(gs))),
+            Err(error) => {
+                self.failed = Some(Failed { name, error });
+                SceneResult::Continue
+            }
+        }
+    }
+
+    fn browser() -> SlotBrowser {
+        SlotBrowser::new("Load game", false, None)
+    }
+
+    /// Shows why the save couldn't be loaded, and offers to move it out of the way.
+    fn ask_move_aside(&mut self, ctx: &mut BTerm) {
+        let Some(failed) = &self.failed else {
+            return;
+        };
+        crate::gui::show_message(
+            ctx,
+            &format!("Failed to load {}", failed.name),
+            &[&failed.error.to_string(), "", "Move the file aside? (y/n)"],
+        );
+        match ctx.key {
+            Some(VirtualKeyCode::Y) => {
+                let name = failed.name.clone();
+                self.failed = None;
+                self.browser = Self::browser();
+                match save_slots::move_aside(&name) {
+                    Ok(path) => self
+                        .browser
+                        .set_message(format!("Moved to {}", path.display())),
+                    Err(err) => self.browser.set_message(err.to_string()),
+                }
+            }
+            Some(VirtualKeyCode::N) | Some(VirtualKeyCode::Escape) => self.failed = None,
+            _ => (),
+        }
+    }
+}
+
+fn load_slot(gs: &mut State, name: &str) -> LoadResult<()> {
+    let mut file = BufReader::new(File::open(save_slots::slot_path(name))?);
+
+    // Load into a new Ecs, so nothing is changed if the load fails.
+    let mut ecs = Ecs::new();
+    ecs.resources.insert(*resource_get!(gs.ecs, Time));
+    crate::load(&mut ecs, &mut file)?;
+    ecs.resources.insert(CurrentSlot(name.to_string()));
+    gs.ecs = ecs;
+
+    Ok(())
+}
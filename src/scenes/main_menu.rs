@@ -1,39 +1,74 @@
 use super::*;
 use crate::gui::MainMenuResult::*;
 use crate::gui::MainMenuState::*;
+use crate::resources::{GameLog, RunSeed};
+use crate::save_slots;
 use crate::State;
 use legion::Schedule;
 
+const MAX_SEED_LEN: usize = 20;
+
 pub(crate) struct MainMenuScene {
     state: crate::gui::MainMenuState,
     schedule: Schedule,
+    /// An autosave left behind by a crash, that the player can resume.
+    recover: Option<(String, crate::save_file::SaveHeader)>,
+    /// The seed being typed for a new game.
+    seed_text: Option<String>,
 }
 
 impl Scene<State> for MainMenuScene {
     fn tick(&mut self, gs: &mut State, ctx: &mut BTerm) -> SceneResult<State> {
         ctx.cls();
+        if let Some((name, header)) = &self.recover {
+            let question = format!(
+                "Resume the game autosaved at {}?",
+                save_slots::format_timestamp(header.timestamp)
+            );
+            match crate::gui::ask_bool(ctx, &question) {
+                (crate::gui::ItemMenuResult::Selected, true) => {
+                    let name = name.clone();
+                    self.recover = None;
+                    return SceneResult::Push(Box::new(super::LoadGameScene::resume(name)));
+                }
+                (crate::gui::ItemMenuResult::NoResponse, _) => return SceneResult::Continue,
+                _ => self.recover = None,
+            }
+        }
         self.schedule
             .execute(&mut gs.ecs.world, &mut gs.ecs.resources);
-        match crate::gui::show_main_menu(ctx, &mut gs.ecs, self.state) {
-            Selected(New) => {
-                crate::new(&mut gs.ecs);
-                {
-                    let mut fil = std::fs::File::create("save.dat").unwrap();
-                    crate::save(gs, &mut fil).unwrap();
+        if let Some(text) = &mut self.seed_text {
+            match crate::gui::ask_text(
+                ctx,
+                "New game",
+                "Leave the seed empty for a random one.",
+                "Seed:",
+                text,
+                MAX_SEED_LEN,
+            ) {
+                crate::gui::ItemMenuResult::Selected => {
+                    let seed = if text.trim().is_empty() {
+                        RunSeed::random()
+                    } else {
+                        RunSeed::from_text(text)
+                    };
+                    return Self::new_game(gs, seed);
                 }
-                SceneResult::Replace(Box::new(super::game::GameScene::new(gs)))
+                crate::gui::ItemMenuResult::Cancel => self.seed_text = None,
+                crate::gui::ItemMenuResult::NoResponse => (),
             }
-            Selected(Quit) => SceneResult::Pop,
-            Selected(Load) => {
-                match std::fs::File::open("save.dat") { Ok(mut fil) => {
-                    crate::load(&mut gs.ecs, &mut fil).unwrap();
-                    std::mem::drop(fil);
-                    let _ = std::fs::remove_file("save.dat");
-                    SceneResult::Replace(Box::new(super::game::GameScene::new(gs)))
-                } _ => {
+            return SceneResult::Continue;
+        }
+        match crate::gui::show_main_menu(ctx, &mut gs.ecs, self.state) {
+            Selected(New) => match gs.seed {
+                Some(seed) => Self::new_game(gs, seed),
+                None => {
+                    self.seed_text = Some(String::new());
                     SceneResult::Continue
-                }}
-            }
+                }
+            },
+            Selected(Quit) => SceneResult::Pop,
+            Selected(Load) => SceneResult::Push(Box::new(super::LoadGameScene::new())),
             NoSelection(state) => {
                 self.state = state;
                 SceneResult::Continue
@@ -56,6 +91,32 @@ impl MainMenuScene {
         MainMenuScene {
             state: crate::gui::MainMenuState::New,
             schedule: Self::build_schedule(),
+            recover: None,
+            seed_text: None,
+        }
+    }
+
+    fn new_game(gs: &mut State, seed: RunSeed) -> SceneResult<State> {
+        crate::new(&mut gs.ecs, seed);
+        let name = save_slots::unused_slot_name();
+        match save_slots::write_slot(&name, |writer| crate::save(gs, writer)) {
+            Ok(()) => gs.ecs.resources.insert(save_slots::CurrentSlot(name)),
+            Err(err) => {
+                // The game can still be played, and saved to another slot.
+                let mut gamelog = resource_get_mut!(gs.ecs, GameLog);
+                gamelog.set_color(RED);
+                gamelog.write_text(format!("Failed to create save slot {name}: {err}"));
+                gamelog.end_of_line();
+            }
+        }
+        SceneResult::Replace(Box::new(super::game::GameScene::new(gs)))
+    }
+
+    /// The first menu, which offers to resume the autosave if the game crashed.
+    pub fn startup() -> MainMenuScene {
+        MainMenuScene {
+            recover: save_slots::autosave_to_recover(),
+            ..Self::new()
         }
     }
 }
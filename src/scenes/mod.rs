@@ -1,10 +1,16 @@
+mod character;
+mod drop_count;
 mod game;
+mod load_game;
 mod main_menu;
+mod pick_up;
 mod save_game;
 mod show_text;
+mod slot_browser;
 
+pub(crate) use load_game::*;
 pub(crate) use main_menu::*;
-// pub(crate) use save_game::*;
+pub(crate) use save_game::*;
 
 use bracket_lib::prelude::*;
 
@@ -14,6 +20,8 @@ pub(crate) enum SceneResult<T> {
     #[allow(dead_code)]
     Push(Box<dyn Scene<T>>),
     Replace(Box<dyn Scene<T>>),
+    /// Removes all scenes and continues with the given one.
+    ReplaceAll(Box<dyn Scene<T>>),
 }
 
 pub(crate) trait Scene<T> {
@@ -33,6 +41,11 @@ impl<T> SceneManager<T> {
         self.scenes.push(scene)
     }
 
+    /// Whether the last scene has ended, ie the player quit from the menu.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
     pub fn tick(&mut self, state: &mut T, ctx: &mut BTerm) {
         if self.scenes.is_empty() {
             ctx.quit();
@@ -50,6 +63,10 @@ impl<T> SceneManager<T> {
                 self.scenes.pop();
                 self.scenes.push(new_scene);
             }
+            SceneResult::ReplaceAll(new_scene) => {
+                self.scenes.clear();
+                self.scenes.push(new_scene);
+            }
         }
     }
 }
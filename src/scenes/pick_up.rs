@@ -0,0 +1,98 @@
+use super::{Scene, SceneResult};
+use crate::State;
+use crate::components::*;
+use crate::ecs::Ecs;
+use crate::messages::WantsToPickupMessage;
+use crate::queues::WantsToPickupQueue;
+use crate::resources::{PlayerEntity, PlayerPosition};
+use crate::{RunState, gui};
+use ::bracket_lib::prelude::*;
+use ::legion::query::component;
+use ::legion::*;
+
+/// Picks any number of items up from the pile the player stands on.
+pub(crate) struct PickUpScene {
+    items: Vec<(Entity, String)>,
+    selected: Vec<bool>,
+}
+
+impl Scene<State> for PickUpScene {
+    fn tick(&mut self, gs: &mut State, ctx: &mut BTerm) -> SceneResult<State> {
+        let mut lines: Vec<String> = self
+            .items
+            .iter()
+            .zip(&self.selected)
+            .enumerate()
+            .map(|(i, ((_, name), selected))| {
+                let mark = if *selected { '+' } else { '-' };
+                format!("{}) {} {}", gui::index_to_letter(i as u8), mark, name)
+            })
+            .collect();
+        lines.push(String::new());
+        lines.push("Letters toggle, comma toggles all.".to_string());
+        lines.push("ENTER to pick up, ESCAPE to cancel".to_string());
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        gui::show_message(ctx, "Pick up", &lines);
+
+        match ctx.key {
+            Some(VirtualKeyCode::Escape) => SceneResult::Pop,
+            Some(VirtualKeyCode::Comma) => {
+                let all = self.selected.iter().all(|selected| *selected);
+                self.selected.fill(!all);
+                SceneResult::Continue
+            }
+            Some(VirtualKeyCode::Return) => {
+                let player_entity = resource_get!(gs.ecs, PlayerEntity).0;
+                let queue = resource_get!(gs.ecs, WantsToPickupQueue);
+                let mut any = false;
+                for ((item, _), _) in self
+                    .items
+                    .iter()
+                    .zip(&self.selected)
+                    .filter(|(_, selected)| **selected)
+                {
+                    queue.send(WantsToPickupMessage {
+                        who: player_entity,
+                        item: *item,
+                    });
+                    any = true;
+                }
+                if any {
+                    gs.ecs.resources.insert(RunState::EnergylessTick);
+                }
+                SceneResult::Pop
+            }
+            Some(key) => {
+                let option = gui::letter_to_option(ctx.shift, key);
+                if let Some(selected) = usize::try_from(option)
+                    .ok()
+                    .and_then(|option| self.selected.get_mut(option))
+                {
+                    *selected = !*selected;
+                }
+                SceneResult::Continue
+            }
+            None => SceneResult::Continue,
+        }
+    }
+}
+
+impl PickUpScene {
+    pub(crate) fn new(ecs: &Ecs) -> Self {
+        let player_pos = resource_get!(ecs, PlayerPosition).0;
+        let items: Vec<_> = <(Entity, &Position, &Name, Option<&Quantity>)>::query()
+            .filter(component::<Item>())
+            .iter(&ecs.world)
+            .filter(|(_, pos, _, _)| pos.0 == player_pos)
+            .take(52)
+            .map(|(entity, _, name, quantity)| {
+                (
+                    *entity,
+                    name.counted(quantity.map_or(1, |quantity| quantity.count)),
+                )
+            })
+            .collect();
+        let selected = vec![false; items.len()];
+        Self { items, selected }
+    }
+}
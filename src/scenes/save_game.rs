@@ -1 +1,142 @@
-// TODO Implement save
+use super::slot_browser::{SlotBrowser, SlotBrowserResult};
+use super::{Scene, SceneResult};
+use crate::ecs::Ecs;
+use crate::resources::{Autosave, GameLog, Time};
+use crate::save_slots::{self, CurrentSlot};
+use crate::State;
+use ::bracket_lib::prelude::*;
+use ::legion::Schedule;
+
+/// How many ticks there are between autosaves, a move takes 100 ticks.
+const AUTOSAVE_TICKS: i64 = 50 * 100;
+
+enum SaveState {
+    Choose(SlotBrowser),
+    ConfirmOverwrite(String),
+    Start(String),
+    Saving(String),
+    Failed(String),
+}
+
+/// Saves the game and returns to the main menu.
+///
+/// If the save fails, the error is shown and the game continues.
+pub(crate) struct SaveGameScene {
+    state: SaveState,
+}
+
+impl Scene<State> for SaveGameScene {
+    fn tick(&mut self, gs: &mut State, ctx: &mut BTerm) -> SceneResult<State> {
+        let mut result = SceneResult::Continue;
+        let next_state = match &mut self.state {
+            SaveState::Choose(browser) => match browser.tick(ctx) {
+                SlotBrowserResult::Continue => None,
+                SlotBrowserResult::Cancel => return SceneResult::Pop,
+                SlotBrowserResult::Selected(name) => {
+                    if current_slot(&gs.ecs).as_deref() == Some(name.as_str()) {
+                        Some(SaveState::Start(name))
+                    } else {
+                        Some(SaveState::ConfirmOverwrite(name))
+                    }
+                }
+                SlotBrowserResult::New(name) => Some(SaveState::Start(name)),
+            },
+            SaveState::ConfirmOverwrite(name) => {
+                match crate::gui::ask_bool(ctx, &format!("Overwrite {name}?")) {
+                    (crate::gui::ItemMenuResult::Selected, true) => {
+                        Some(SaveState::Start(name.clone()))
+                    }
+                    (crate::gui::ItemMenuResult::NoResponse, _) => None,
+                    _ => Some(Self::choose(&gs.ecs)),
+                }
+            }
+            SaveState::Start(name) => {
+                // Show the dialog before the (possibly slow) saving starts.
+                crate::gui::show_message(ctx, "Save game", &[&format!("Saving {name}...")]);
+                Some(SaveState::Saving(name.clone()))
+            }
+            SaveState::Saving(name) => match save_game(gs, name) {
+                Ok(()) => {
+                    // The autosave is older than the save, don't offer it.
+                    save_slots::mark_clean_exit_or_complain();
+                    result = SceneResult::ReplaceAll(Box::new(super::MainMenuScene::new()));
+                    None
+                }
+                Err(err) => Some(SaveState::Failed(format!("Failed to save: {err}"))),
+            },
+            SaveState::Failed(msg) => {
+                crate::gui::show_message(ctx, "Save game", &[msg]);
+                match ctx.key {
+                    Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::Escape) => {
+                        return SceneResult::Pop;
+                    }
+                    _ => None,
+                }
+            }
+        };
+        if let Some(next_state) = next_state {
+            self.state = next_state;
+        }
+        result
+    }
+}
+
+impl SaveGameScene {
+    pub(crate) fn new(ecs: &Ecs) -> Self {
+        Self {
+            state: Self::choose(ecs),
+        }
+    }
+
+    fn choose(ecs: &Ecs) -> SaveState {
+        SaveState::Choose(SlotBrowser::new(
+            "Save game",
+            true,
+            current_slot(ecs).as_deref(),
+        ))
+    }
+}
+
+fn current_slot(ecs: &Ecs) -> Option<String> {
+    ecs.resources
+        .get::<CurrentSlot>()
+        .map(|slot| slot.0.clone())
+}
+
+fn save_game(gs: &mut State, name: &str) -> crate::Result<()> {
+    write_game(gs, name)?;
+    gs.ecs.resources.insert(CurrentSlot(name.to_string()));
+
+    Ok(())
+}
+
+fn write_game(gs: &mut State, name: &str) -> crate::Result<()> {
+    // Write all pending messages to the log before it is saved.
+    Schedule::builder()
+        .add_system(crate::systems::output_system())
+        .build()
+        .execute(&mut gs.ecs.world, &mut gs.ecs.resources);
+
+    save_slots::write_slot(name, |writer| crate::save(gs, writer))
+}
+
+/// Saves the game to the autosave slot, if it is time for it.
+pub(crate) fn autosave(gs: &mut State) {
+    let tick = resource_get!(gs.ecs, Time).tick;
+    let autosave = *resource_get!(gs.ecs, Autosave);
+    if !autosave.requested && tick < autosave.last_tick + AUTOSAVE_TICKS {
+        return;
+    }
+
+    let result = write_game(gs, save_slots::AUTOSAVE_SLOT);
+    gs.ecs.resources.insert(Autosave {
+        last_tick: tick,
+        requested: false,
+    });
+    if let Err(err) = result {
+        let mut gamelog = resource_get_mut!(gs.ecs, GameLog);
+        gamelog.set_color(RED);
+        gamelog.write_text(format!("Autosave failed: {err}"));
+        gamelog.end_of_line();
+    }
+}
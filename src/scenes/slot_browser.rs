@@ -0,0 +1,211 @@
+use crate::gui::{self, ItemMenuResult};
+use crate::save_slots::{self, SaveSlot};
+use ::bracket_lib::prelude::*;
+
+const MAX_NAME_LEN: usize = 16;
+
+pub(crate) enum SlotBrowserResult {
+    Continue,
+    Cancel,
+    /// An existing slot was chosen.
+    Selected(String),
+    /// A new slot should be created with the given name.
+    New(String),
+}
+
+enum Mode {
+    Browse,
+    ConfirmDelete,
+    Rename(String),
+    NewName(String),
+}
+
+/// Lists the save slots and lets the player pick, rename or delete them.
+pub(crate) struct SlotBrowser {
+    title: &'static str,
+    allow_new: bool,
+    slots: Vec<SaveSlot>,
+    selected: usize,
+    mode: Mode,
+    message: Option<String>,
+}
+
+impl SlotBrowser {
+    /// `allow_new` - the player can choose to create a new slot.
+    /// `current` - the initially selected slot.
+    pub(crate) fn new(title: &'static str, allow_new: bool, current: Option<&str>) -> Self {
+        let mut browser = Self {
+            title,
+            allow_new,
+            slots: vec![],
+            selected: 0,
+            mode: Mode::Browse,
+            message: None,
+        };
+        browser.refresh(current);
+        browser
+    }
+
+    fn refresh(&mut self, select: Option<&str>) {
+        self.slots = save_slots::list_slots();
+        self.selected = select
+            .and_then(|name| self.slots.iter().position(|slot| slot.name == name))
+            .unwrap_or(0);
+    }
+
+    /// Shows a message below the slots.
+    pub(crate) fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    fn selected_name(&self) -> Option<String> {
+        self.slots.get(self.selected).map(|slot| slot.name.clone())
+    }
+
+    pub(crate) fn tick(&mut self, ctx: &mut BTerm) -> SlotBrowserResult {
+        let (screen_width, _) = ctx.get_char_size();
+        let width = screen_width as i32 - 4;
+        let count = i32::max(1, self.slots.len() as i32);
+        let (x, y) = (2, 4);
+
+        ctx.draw_box(
+            x,
+            y,
+            width - 1,
+            count + 5,
+            RGB::named(WHITE),
+            RGB::named(BLACK),
+        );
+        ctx.print_color(x + 3, y, RGB::named(YELLOW), RGB::named(BLACK), self.title);
+
+        if self.slots.is_empty() {
+            ctx.print(x + 2, y + 2, "There are no saved games.");
+        }
+        for (i, slot) in self.slots.iter().enumerate() {
+            let (fg, bg) = if i == self.selected {
+                (RGB::named(BLACK), RGB::named(WHITE))
+            } else {
+                (RGB::named(WHITE), RGB::named(BLACK))
+            };
+            ctx.print_color(x + 2, y + 2 + i as i32, fg, bg, slot.describe());
+        }
+
+        let help = if self.allow_new {
+            "ENTER select  N new  R rename  D delete  ESCAPE cancel"
+        } else {
+            "ENTER select  R rename  D delete  ESCAPE cancel"
+        };
+        ctx.print_color(
+            x + 3,
+            y + count + 5,
+            RGB::named(YELLOW),
+            RGB::named(BLACK),
+            help,
+        );
+        if let Some(message) = &self.message {
+            ctx.print_color(
+                x + 2,
+                y + count + 3,
+                RGB::named(RED),
+                RGB::named(BLACK),
+                message,
+            );
+        }
+
+        let input_y = y + count + 3;
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => self.browse(ctx),
+            Mode::ConfirmDelete => {
+                let name = self.selected_name().unwrap_or_default();
+                match gui::ask_bool(ctx, &format!("Delete {name}?")) {
+                    (ItemMenuResult::Selected, true) => {
+                        self.message = save_slots::delete_slot(&name)
+                            .err()
+                            .map(|err| err.to_string());
+                        self.refresh(None);
+                    }
+                    (ItemMenuResult::NoResponse, _) => self.mode = Mode::ConfirmDelete,
+                    _ => (),
+                }
+                SlotBrowserResult::Continue
+            }
+            Mode::Rename(mut new_name) => {
+                match gui::edit_text(
+                    ctx,
+                    x + 2,
+                    input_y,
+                    "New name:",
+                    &mut new_name,
+                    MAX_NAME_LEN,
+                ) {
+                    ItemMenuResult::Selected => {
+                        let old_name = self.selected_name().unwrap_or_default();
+                        let new_name = save_slots::sanitize_name(&new_name);
+                        if new_name.is_empty() {
+                            self.message = Some("The name can't be empty".to_string());
+                        } else {
+                            self.message = save_slots::rename_slot(&old_name, &new_name)
+                                .err()
+                                .map(|err| err.to_string());
+                            self.refresh(Some(&new_name));
+                        }
+                    }
+                    ItemMenuResult::Cancel => (),
+                    ItemMenuResult::NoResponse => self.mode = Mode::Rename(new_name),
+                }
+                SlotBrowserResult::Continue
+            }
+            Mode::NewName(mut name) => {
+                match gui::edit_text(ctx, x + 2, input_y, "Name:", &mut name, MAX_NAME_LEN) {
+                    ItemMenuResult::Selected => {
+                        let name = save_slots::sanitize_name(&name);
+                        if name.is_empty() {
+                            self.message = Some("The name can't be empty".to_string());
+                        } else if save_slots::slot_path(&name).exists() {
+                            self.message = Some(format!("There is already a save called {name}"));
+                        } else {
+                            return SlotBrowserResult::New(name);
+                        }
+                    }
+                    ItemMenuResult::Cancel => (),
+                    ItemMenuResult::NoResponse => self.mode = Mode::NewName(name),
+                }
+                SlotBrowserResult::Continue
+            }
+        }
+    }
+
+    fn browse(&mut self, ctx: &mut BTerm) -> SlotBrowserResult {
+        let count = self.slots.len();
+        match ctx.key {
+            Some(VirtualKeyCode::Escape) => return SlotBrowserResult::Cancel,
+            Some(VirtualKeyCode::Down) | Some(VirtualKeyCode::J) if count > 0 => {
+                self.selected = (self.selected + 1) % count;
+            }
+            Some(VirtualKeyCode::Up) | Some(VirtualKeyCode::K) if count > 0 => {
+                self.selected = (self.selected + count - 1) % count;
+            }
+            Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::Space) => {
+                if let Some(name) = self.selected_name() {
+                    return SlotBrowserResult::Selected(name);
+                }
+            }
+            Some(VirtualKeyCode::N) if self.allow_new => {
+                self.message = None;
+                self.mode = Mode::NewName(save_slots::unused_slot_name());
+            }
+            Some(VirtualKeyCode::R) => {
+                if let Some(name) = self.selected_name() {
+                    self.message = None;
+                    self.mode = Mode::Rename(name);
+                }
+            }
+            Some(VirtualKeyCode::D) if count > 0 => {
+                self.message = None;
+                self.mode = Mode::ConfirmDelete;
+            }
+            _ => (),
+        }
+        SlotBrowserResult::Continue
+    }
+}
@@ -1,14 +1,13 @@
 use crate::components::*;
 use crate::ecs::Ecs;
-use crate::resources::MAP_WIDTH;
+use crate::raws::{self, ItemDef, MonsterDef, TrapDef};
+use crate::resources::Time;
 use crate::MapPosition;
 use bracket_lib::prelude::*;
 use legion::*;
 
-pub(crate) const MAX_MONSTERS: i32 = 3;
-pub(crate) const MAX_ITEMS: i32 = 4;
-
 pub(crate) fn player(ecs: &mut Ecs, player_x: i32, player_y: i32) -> Entity {
+    let tick = ecs.resources.get_mut_or_default::<Time>().tick;
     ecs.world.push((
         Energy { energy: 0 },
         Position(MapPosition {
@@ -37,218 +36,187 @@ pub(crate) fn player(ecs: &mut Ecs, player_x: i32, player_y: i32) -> Entity {
             power: 5,
             defense: 2,
         },
+        MeleeDamage::UNARMED,
+        Attributes::default(),
+        Experience::default(),
+        HungerClock::well_fed(tick),
     ))
 }
 
-/// Spawns a random monster at a given location
-pub(crate) fn random_monster(ecs: &mut Ecs, x: i32, y: i32) {
-    let roll: i32;
-    {
-        let mut rng = resource_get_mut!(ecs, RandomNumberGenerator);
-        roll = rng.roll_dice(1, 2);
-    }
-    match roll {
-        1 => lamotte(ecs, x, y),
-        _ => janouch(ecs, x, y),
-    }
-}
-
-fn lamotte(ecs: &mut Ecs, x: i32, y: i32) {
-    monster(ecs, x, y, to_cp437('l'), "Lamotte");
-}
-fn janouch(ecs: &mut Ecs, x: i32, y: i32) {
-    monster(ecs, x, y, to_cp437('j'), "Janouch");
-}
-
-fn monster<S: ToString>(ecs: &mut Ecs, x: i32, y: i32, glyph: u16, name: S) {
-    ecs.world.push((
+/// Spawns a monster from its raw definition.
+pub(crate) fn monster(ecs: &mut Ecs, def: &MonsterDef, x: i32, y: i32) {
+    let monster = ecs.world.push((
         Energy { energy: 0 },
         Position(MapPosition { x, y }),
-        Renderable {
-            glyph,
-            fg: RGB::named(RED),
-            bg: RGB::named(BLACK),
-            render_order: 1,
-        },
+        def.renderable.clone(),
         Viewshed {
             visible_tiles: Vec::new(),
-            range: 8,
+            range: def.vision,
             dirty: true,
         },
         Monster {},
-        Name {
-            name: name.to_string(),
-            proper_name: true,
-        },
+        def.name.clone(),
         BlocksTile {},
-        CombatStats {
-            max_hp: 16,
-            hp: 16,
-            defense: 1,
-            power: 4,
-        },
+        def.stats.clone(),
+        def.melee_damage.clone(),
+        def.xp.clone(),
     ));
-}
+    let mut entry = ecs.world.entry(monster).unwrap();
+    if let Some(gender) = def.gender {
+        entry.add_component(gender);
+    }
+    if let Some(resistances) = &def.resistances {
+        entry.add_component(resistances.clone());
+    }
 
-/// Spawns a random monster at a given location
-pub(crate) fn random_item(ecs: &mut Ecs, x: i32, y: i32) {
-    let roll = {
-        let mut rng = resource_get_mut!(ecs, RandomNumberGenerator);
-        rng.roll_dice(1, 5)
-    };
-    match roll {
-        1 => health_potion(ecs, x, y),
-        2 => magic_missile_scroll(ecs, x, y),
-        3 => fireball_scroll(ecs, x, y),
-        4 => apple(ecs, x, y),
-        _ => ball(ecs, x, y),
+    let raws = raws::get();
+    for name in &def.equipment {
+        if let Some(item_def) = raws.item(name) {
+            let item = item_entity(ecs, item_def);
+            let mut entry = ecs.world.entry(item).unwrap();
+            if let Some(equippable) = &item_def.equippable {
+                entry.add_component(Equipped {
+                    owner: monster,
+                    slot: equippable.slot,
+                });
+            }
+        }
     }
 }
 
-fn health_potion(ecs: &mut Ecs, x: i32, y: i32) {
-    ecs.world.push((
-        Position(MapPosition { x, y }),
-        Renderable {
-            glyph: to_cp437('¡'),
-            fg: RGB::named(MAGENTA),
-            bg: RGB::named(BLACK),
-            render_order: 2,
-        },
-        Name {
-            name: "health potion".to_string(),
-            proper_name: false,
-        },
-        Item {},
-        Consumable {},
-        HealthProvider { heal_amount: 8 },
-    ));
+/// Spawns the monster, item or trap with the given name.
+pub(crate) fn spawn_named(ecs: &mut Ecs, name: &str, x: i32, y: i32) {
+    let raws = raws::get();
+    if let Some(def) = raws.monster(name) {
+        monster(ecs, def, x, y);
+    } else if let Some(def) = raws.item(name) {
+        item(ecs, def, x, y);
+    } else if let Some(def) = raws.trap(name) {
+        trap(ecs, def, x, y);
+    }
 }
 
-fn apple(ecs: &mut Ecs, x: i32, y: i32) {
-    ecs.world.push((
-        Position(MapPosition { x, y }),
-        Renderable {
-            glyph: to_cp437('°'),
-            fg: RGB::named(YELLOW),
-            bg: RGB::named(BLACK),
-            render_order: 2,
-        },
-        Name {
-            name: "apple".to_string(),
-            proper_name: false,
-        },
-        Item {},
-        Consumable {},
-        HealthProvider { heal_amount: 5 },
-    ));
+/// Spawns an item from its raw definition.
+pub(crate) fn item(ecs: &mut Ecs, def: &ItemDef, x: i32, y: i32) {
+    let entity = item_entity(ecs, def);
+    ecs.world
+        .entry(entity)
+        .unwrap()
+        .add_component(Position(MapPosition { x, y }));
 }
 
-fn ball(ecs: &mut Ecs, x: i32, y: i32) {
-    ecs.world.push((
-        Position(MapPosition { x, y }),
-        Renderable {
-            glyph: to_cp437('*'),
-            fg: RGB::named(PURPLE),
-            bg: RGB::named(BLACK),
-            render_order: 2,
-        },
-        Name {
-            name: "ball".to_string(),
-            proper_name: false,
-        },
-        Item {},
-    ));
+/// Creates an item that isn't anywhere yet.
+fn item_entity(ecs: &mut Ecs, def: &ItemDef) -> Entity {
+    let entity = ecs
+        .world
+        .push((def.renderable.clone(), def.name.clone(), Item {}));
+    let mut entry = ecs.world.entry(entity).unwrap();
+    if def.stackable {
+        entry.add_component(Quantity { count: 1 });
+    }
+    add_item_components(&mut entry, def);
+    entity
 }
 
-fn magic_missile_scroll(ecs: &mut Ecs, x: i32, y: i32) {
-    ecs.world.push((
-        Position(MapPosition { x, y }),
-        Renderable {
-            glyph: to_cp437('?'),
-            fg: RGB::named(CYAN),
-            bg: RGB::named(BLACK),
-            render_order: 2,
-        },
-        Name {
-            name: "magic missile scroll".to_string(),
-            proper_name: false,
-        },
-        Item {},
-        Consumable {},
-        Ranged { range: 6 },
-        InflictsDamage { damage: 8 },
-    ));
+/// Gives the item the components of its definition, besides the
+/// `Renderable`, `Name`, `Item` and `Quantity` it was created with.
+pub(crate) fn add_item_components(entry: &mut legion::world::Entry, def: &ItemDef) {
+    if def.consumable {
+        entry.add_component(Consumable {});
+    }
+    if let Some(heal) = &def.heal {
+        entry.add_component(heal.clone());
+    }
+    if let Some(damage) = &def.damage {
+        entry.add_component(damage.clone());
+    }
+    if let Some(ranged) = &def.ranged {
+        entry.add_component(ranged.clone());
+    }
+    if let Some(area_of_effect) = &def.area_of_effect {
+        entry.add_component(area_of_effect.clone());
+    }
+    if let Some(area_shape) = def.area_shape {
+        entry.add_component(area_shape);
+    }
+    if let Some(equippable) = &def.equippable {
+        entry.add_component(equippable.clone());
+    }
+    if let Some(power_bonus) = &def.power_bonus {
+        entry.add_component(power_bonus.clone());
+    }
+    if let Some(defense_bonus) = &def.defense_bonus {
+        entry.add_component(defense_bonus.clone());
+    }
+    if let Some(melee_damage) = &def.melee_damage {
+        entry.add_component(melee_damage.clone());
+    }
+    if let Some(missile_damage) = &def.missile_damage {
+        entry.add_component(missile_damage.clone());
+    }
+    if let Some(ammunition) = &def.ammunition {
+        entry.add_component(ammunition.clone());
+    }
+    if let Some(launcher) = &def.launcher {
+        entry.add_component(launcher.clone());
+    }
+    if let Some(status) = &def.status {
+        entry.add_component(status.clone());
+    }
+    if def.food {
+        entry.add_component(ProvidesFood {});
+    }
 }
 
-fn fireball_scroll(ecs: &mut Ecs, x: i32, y: i32) {
+/// Spawns a hidden trap from its raw definition.
+pub(crate) fn trap(ecs: &mut Ecs, def: &TrapDef, x: i32, y: i32) {
     ecs.world.push((
         Position(MapPosition { x, y }),
-        Renderable {
-            glyph: to_cp437('?'),
-            fg: RGB::named(ORANGE),
-            bg: RGB::named(BLACK),
-            render_order: 2,
-        },
-        Name {
-            name: "fireball scroll".to_string(),
-            proper_name: false,
-        },
-        Item {},
-        Consumable {},
-        Ranged { range: 6 },
-        InflictsDamage { damage: 20 },
-        AreaOfEffect { radius: 3 },
+        def.renderable.clone(),
+        def.name.clone(),
+        def.trap.clone(),
+        Hidden {},
     ));
 }
 
-/// Fills a room with stuff!
-pub(crate) fn spawn_room(ecs: &mut Ecs, room: &Rect) {
-    let mut monster_spawn_points: Vec<usize> = Vec::new();
-    let mut item_spawn_points: Vec<usize> = Vec::new();
-
-    // Scope to keep the borrow checker happy
-    {
-        let mut rng = resource_get_mut!(ecs, RandomNumberGenerator);
-        let num_monsters = rng.roll_dice(1, MAX_MONSTERS + 2) - 3;
-        let num_items = rng.roll_dice(1, MAX_ITEMS + 2) - 3;
-
-        for _i in 0..num_monsters {
-            let mut added = false;
-            while !added {
-                let x = room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1));
-                let y = room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1));
-                let idx = ((y * MAP_WIDTH) + x) as usize;
-                if !monster_spawn_points.contains(&idx) {
-                    monster_spawn_points.push(idx);
-                    added = true;
+/// Fills a region of floor tiles with stuff from the spawn tables!
+pub(crate) fn spawn_region(
+    ecs: &mut Ecs,
+    rng: &mut RandomNumberGenerator,
+    region: &[MapPosition],
+    depth: i32,
+) {
+    let raws = raws::get();
+    let mut spawns: Vec<(&str, i32, i32)> = Vec::new();
+
+    for table in [&raws.monster_spawns, &raws.item_spawns, &raws.trap_spawns] {
+        // Monsters, items and traps can share a tile, but not with their own kind.
+        let mut spawn_points: Vec<MapPosition> = Vec::new();
+        let num_groups = rng.roll_dice(1, table.max_per_room + 3) - 3;
+
+        for _i in 0..num_groups {
+            let Some(entry) = table.roll(rng, depth) else {
+                break;
+            };
+            let group_size = rng.range(entry.min_group, entry.max_group + 1);
+            for _j in 0..group_size {
+                if spawn_points.len() >= region.len() {
+                    break;
                 }
-            }
-        }
-
-        for _i in 0..num_items {
-            let mut added = false;
-            while !added {
-                let x = room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1));
-                let y = room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1));
-                let idx = (y * MAP_WIDTH + x) as usize;
-                if !item_spawn_points.contains(&idx) {
-                    item_spawn_points.push(idx);
-                    added = true;
+                loop {
+                    let pos = region[rng.range(0, region.len() as i32) as usize];
+                    if !spawn_points.contains(&pos) {
+                        spawn_points.push(pos);
+                        spawns.push((entry.name.as_str(), pos.x, pos.y));
+                        break;
+                    }
                 }
             }
         }
     }
 
-    // Actually spawn the monsters
-    for idx in monster_spawn_points.iter() {
-        let x = *idx % MAP_WIDTH as usize;
-        let y = *idx / MAP_WIDTH as usize;
-        random_monster(ecs, x as i32, y as i32);
-    }
-    // Actually spawn the items
-    for idx in item_spawn_points.iter() {
-        let x = *idx % MAP_WIDTH as usize;
-        let y = *idx / MAP_WIDTH as usize;
-        random_item(ecs, x as i32, y as i32);
+    // Actually spawn the monsters and items
+    for (name, x, y) in spawns {
+        spawn_named(ecs, name, x, y);
     }
 }
@@ -1,8 +1,17 @@
 use crate::positions::{MapPosition, ScreenPosition};
-use crate::resources::{Camera, PlayerPosition};
-use crate::resources::{MAP_HEIGHT, MAP_WIDTH};
+use crate::resources::{Camera, Map, PlayerPosition};
 use ::legion::*;
 
+/// Keeps as much of the map in view as possible, maps smaller than the
+/// view are centered in it.
+fn clamp_offset(offset: i32, view_size: i32, map_size: i32) -> i32 {
+    if map_size <= view_size {
+        (map_size - view_size) / 2
+    } else {
+        offset.clamp(0, map_size - view_size)
+    }
+}
+
 fn diff_to_interval(v: i32, min: i32, max: i32) -> i32 {
     if v < min {
         min - v
@@ -17,18 +26,19 @@ fn diff_to_interval(v: i32, min: i32, max: i32) -> i32 {
 pub(crate) fn camera_update(
     #[resource] camera: &mut Camera,
     #[resource] player_position: &PlayerPosition,
+    #[resource] map: &Map,
 ) {
     let pos: MapPosition = player_position.0;
 
     if !camera.is_in_view(pos) {
-        camera.center(*player_position);
+        camera.center(*player_position, map);
     } else if camera.old_player_pos != pos {
         let screen_pos = pos - camera.offset;
         let (dx, dy);
         dx = diff_to_interval(screen_pos.x, camera.w / 3, 2 * camera.w / 3);
         dy = diff_to_interval(screen_pos.y, camera.h / 3, 2 * camera.h / 3);
 
-        camera.move_view(-dx, -dy);
+        camera.move_view(-dx, -dy, map);
 
         camera.old_player_pos = pos;
     }
@@ -46,22 +56,22 @@ impl Camera {
     }
 
     // Hard jump to new position
-    pub fn center(&mut self, pos: PlayerPosition) {
+    pub fn center(&mut self, pos: PlayerPosition, map: &Map) {
         self.old_player_pos = pos.0;
         self.sub_tile_offset = (0.0, 0.0);
 
         let (x, y) = (
-            i32::min(MAP_WIDTH - self.w, i32::max(0, (pos.0).x - self.w / 2)),
-            i32::min(MAP_HEIGHT - self.h, i32::max(0, (pos.0).y - self.h / 2)),
+            clamp_offset((pos.0).x - self.w / 2, self.w, map.width),
+            clamp_offset((pos.0).y - self.h / 2, self.h, map.height),
         );
 
         self.offset = MapPosition { x, y };
     }
 
-    pub fn move_view(&mut self, dx: i32, dy: i32) {
+    pub fn move_view(&mut self, dx: i32, dy: i32, map: &Map) {
         let (x, y) = (
-            i32::min(MAP_WIDTH - self.w, i32::max(0, self.offset.x + dx)),
-            i32::min(MAP_HEIGHT - self.h, i32::max(0, self.offset.y + dy)),
+            clamp_offset(self.offset.x + dx, self.w, map.width),
+            clamp_offset(self.offset.y + dy, self.h, map.height),
         );
 
         self.offset = MapPosition { x, y };
@@ -96,3 +106,59 @@ impl Camera {
         self.h
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player_at(x: i32, y: i32) -> PlayerPosition {
+        PlayerPosition(MapPosition { x, y })
+    }
+
+    #[test]
+    fn center_on_large_maps() {
+        let map = Map::new(120, 60);
+        let mut camera = Camera::new(80, 43);
+
+        camera.center(player_at(60, 30), &map);
+        assert_eq!(camera.offset, MapPosition { x: 20, y: 9 });
+
+        // The view stays inside the map at its edges.
+        camera.center(player_at(2, 1), &map);
+        assert_eq!(camera.offset, MapPosition { x: 0, y: 0 });
+        camera.center(player_at(118, 58), &map);
+        assert_eq!(camera.offset, MapPosition { x: 40, y: 17 });
+        camera.move_view(5, 5, &map);
+        assert_eq!(camera.offset, MapPosition { x: 40, y: 17 });
+    }
+
+    #[test]
+    fn center_maps_smaller_than_the_view() {
+        let map = Map::new(40, 21);
+        let mut camera = Camera::new(80, 43);
+
+        for pos in [player_at(1, 1), player_at(20, 10), player_at(38, 19)] {
+            camera.center(pos, &map);
+            assert_eq!(camera.offset, MapPosition { x: -20, y: -11 });
+            assert!(camera.is_in_view(pos.0));
+        }
+        camera.move_view(-3, 4, &map);
+        assert_eq!(camera.offset, MapPosition { x: -20, y: -11 });
+
+        let corner = camera.transform_map_pos(MapPosition { x: 0, y: 0 });
+        assert_eq!(corner, ScreenPosition { x: 20, y: 11 });
+        let far_corner = camera.transform_map_pos(MapPosition { x: 39, y: 20 });
+        assert_eq!(far_corner, ScreenPosition { x: 59, y: 31 });
+    }
+
+    #[test]
+    fn small_maps_only_scroll_along_their_large_side() {
+        let map = Map::new(200, 20);
+        let mut camera = Camera::new(80, 43);
+
+        camera.center(player_at(150, 10), &map);
+        assert_eq!(camera.offset, MapPosition { x: 110, y: -11 });
+        camera.move_view(-30, -30, &map);
+        assert_eq!(camera.offset, MapPosition { x: 80, y: -11 });
+    }
+}
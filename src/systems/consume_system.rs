@@ -1,9 +1,17 @@
+use super::inventory_system::split_stack;
 use crate::components::*;
-use crate::messages::{ReceiveHealthMessage, RemoveItemMessage, SufferDamageMessage};
-use crate::queues::{ReceiveHealthQueue, RemoveItemQueue, SufferDamageQueue, WantsToUseQueue};
-use crate::resources::{Camera, Map, OutputQueue};
+use crate::messages::{
+    EatFoodMessage, InflictStatusMessage, ParticleMessage, ReceiveHealthMessage, RemoveItemMessage,
+    SufferDamageMessage,
+};
+use crate::queues::{
+    EatFoodQueue, InflictStatusQueue, ParticleQueue, ReceiveHealthQueue, RemoveItemQueue,
+    SufferDamageQueue, WantsToUseQueue,
+};
+use crate::resources::{Map, OutputQueue};
 use crate::PlayerEntity;
-use crate::ScreenPosition;
+use bracket_lib::prelude::{ORANGE, RGB, to_cp437};
+use legion::systems::CommandBuffer;
 use legion::*;
 
 #[allow(clippy::too_many_arguments)]
@@ -12,60 +20,78 @@ use legion::*;
 #[read_component(InflictsDamage)]
 #[read_component(Item)]
 #[read_component(AreaOfEffect)]
+#[read_component(AreaShape)]
+#[read_component(InflictsStatus)]
+#[read_component(ProvidesFood)]
+#[read_component(Position)]
+#[read_component(Renderable)]
+#[read_component(Name)]
+#[write_component(Quantity)]
 pub(crate) fn consume(
-    world: &legion::world::SubWorld,
+    world: &mut legion::world::SubWorld,
+    cb: &mut CommandBuffer,
     #[resource] receive_health_queue: &ReceiveHealthQueue,
     #[resource] remove_item_queue: &RemoveItemQueue,
     #[resource] suffer_damage_queue: &SufferDamageQueue,
+    #[resource] inflict_status_queue: &InflictStatusQueue,
+    #[resource] eat_food_queue: &EatFoodQueue,
+    #[resource] particle_queue: &ParticleQueue,
     #[resource] wants_to_use_queue: &mut WantsToUseQueue,
     #[resource] player_entity: &PlayerEntity,
     #[resource] output: &OutputQueue,
-    #[resource] camera: &Camera,
     #[resource] map: &Map,
 ) {
     for (user_entity, wants_to_use_item, wants_to_use_target) in wants_to_use_queue
         .try_iter()
         .map(|msg| (msg.who, msg.item, msg.target))
     {
+        // Only one of a stack is used up.
+        let consumed = split_stack(world, cb, wants_to_use_item, 1);
         let item_entry = world.entry_ref(wants_to_use_item);
 
         match item_entry { Ok(item_entry) => {
             output
                 .the(user_entity)
                 .v(user_entity, "consume")
-                .the(wants_to_use_item);
+                .the(consumed);
 
+            let fg = item_entry
+                .get_component::<Renderable>()
+                .map_or(RGB::named(ORANGE), |renderable| renderable.fg);
             let mut targets: Vec<Entity> = Vec::new();
             match wants_to_use_target {
                 None => {
                     targets.push(player_entity.0);
                 }
                 Some(target) => {
-                    let area_effect = item_entry.get_component::<AreaOfEffect>();
-                    if let Ok(area_effect) = area_effect {
+                    let user_pos = world
+                        .entry_ref(user_entity)
+                        .ok()
+                        .and_then(|entry| entry.get_component::<Position>().ok().map(|pos| pos.0))
+                        .unwrap_or(target);
+                    if let Ok(area_effect) = item_entry.get_component::<AreaOfEffect>() {
                         // AoE
-                        let screen_point = camera.transform_map_pos(target).into();
-                        for tile_point in bracket_lib::prelude::field_of_view(
-                            screen_point,
-                            area_effect.radius,
-                            map,
-                        )
-                        .iter()
-                        .filter_map(|p| {
-                            if p.x >= 0 && p.x < camera.width() && p.y >= 0 && p.y < camera.height()
-                            {
-                                Some(ScreenPosition { x: p.x, y: p.y })
-                            } else {
-                                None
-                            }
-                        }) {
-                            let idx = map.map_pos_to_idx(camera.transform_screen_pos(tile_point));
-                            for mob in &map.tile_content[idx] {
-                                targets.push(*mob);
-                            }
+                        let shape = item_entry
+                            .get_component::<AreaShape>()
+                            .ok()
+                            .copied()
+                            .unwrap_or_default();
+                        let tiles = map.area(area_effect, shape, user_pos, target);
+                        for pos in &tiles {
+                            targets.extend(&map.tile_content[map.map_pos_to_idx(*pos)]);
                         }
+                        let center = match shape {
+                            AreaShape::Burst => target,
+                            AreaShape::Cone | AreaShape::Line => user_pos,
+                        };
+                        particle_queue.send(ParticleMessage::Blast { center, tiles, fg });
                     } else {
                         // Single target in tile
+                        particle_queue.send(ParticleMessage::Missile {
+                            path: map.line_of_fire(user_pos, target),
+                            glyph: to_cp437('*'),
+                            fg,
+                        });
                         let idx = map.map_pos_to_idx(target);
                         for mob in &map.tile_content[idx] {
                             targets.push(*mob);
@@ -73,6 +99,20 @@ pub(crate) fn consume(
                     }
                 }
             }
+            if let Ok(status) = item_entry.get_component::<InflictsStatus>() {
+                for target in &targets {
+                    inflict_status_queue.send(InflictStatusMessage {
+                        target: *target,
+                        kind: status.kind,
+                        turns: status.turns,
+                    });
+                }
+            }
+            if item_entry.get_component::<ProvidesFood>().is_ok() {
+                for target in &targets {
+                    eat_food_queue.send(EatFoodMessage { who: *target });
+                }
+            }
             let heal_amount: Option<_> = {
                 match item_entry.get_component::<HealthProvider>() { Ok(health) => {
                     Some(health.heal_amount)
@@ -91,6 +131,7 @@ pub(crate) fn consume(
                     receive_health_queue.send(ReceiveHealthMessage {
                         target,
                         amount: heal_amount,
+                        raise_max_hp: true,
                     });
                 }
             } else {
@@ -110,13 +151,12 @@ pub(crate) fn consume(
                         suffer_damage_queue.send(SufferDamageMessage {
                             target,
                             amount: item_damage,
+                            attacker: Some(user_entity),
                         });
                     }
                 }
             }
-            remove_item_queue.send(RemoveItemMessage {
-                target: wants_to_use_item,
-            });
+            remove_item_queue.send(RemoveItemMessage { target: consumed });
         } _ => if user_entity == player_entity.0 {
             output.s("You cannot use").the(wants_to_use_item);
         }}
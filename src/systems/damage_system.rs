@@ -1,29 +1,55 @@
 use crate::components::*;
-use crate::messages::{ReceiveHealthMessage, RemoveItemMessage, SufferDamageMessage};
-use crate::queues::{ReceiveHealthQueue, RemoveItemQueue, SufferDamageQueue};
+use crate::messages::{
+    GainExperienceMessage, ReceiveHealthMessage, RemoveItemMessage, SufferDamageMessage,
+};
+use crate::queues::{GainExperienceQueue, ReceiveHealthQueue, RemoveItemQueue, SufferDamageQueue};
 use crate::resources::{Map, OutputQueue, PlayerEntity, Time};
 use legion::world::SubWorld;
-use legion::{system, systems::CommandBuffer, Entity, EntityStore};
+use legion::{system, systems::CommandBuffer, Entity, EntityStore, IntoQuery};
+use std::collections::HashMap;
 
 #[system]
 #[write_component(CombatStats)]
-pub(crate) fn damage(world: &mut SubWorld, #[resource] queue: &SufferDamageQueue) {
-    for SufferDamageMessage { target, amount } in queue.try_iter() {
+pub(crate) fn damage(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] queue: &SufferDamageQueue,
+    #[resource] player_entity: &PlayerEntity,
+) {
+    for SufferDamageMessage {
+        target,
+        amount,
+        attacker,
+    } in queue.try_iter()
+    {
         if let Ok(ref mut entry) = world.entry_mut(target) {
             if let Ok(stats) = entry.get_component_mut::<CombatStats>() {
                 stats.hp -= amount;
             }
         }
+        if let Some(attacker) = attacker {
+            cb.add_component(
+                target,
+                LastHitBy {
+                    by_player: attacker == player_entity.0,
+                },
+            );
+        }
     }
 }
 
 #[system]
 #[write_component(CombatStats)]
 pub(crate) fn health(world: &mut SubWorld, #[resource] receive_health_queue: &ReceiveHealthQueue) {
-    for ReceiveHealthMessage { target, amount } in receive_health_queue.try_iter() {
+    for ReceiveHealthMessage {
+        target,
+        amount,
+        raise_max_hp,
+    } in receive_health_queue.try_iter()
+    {
         if let Ok(ref mut entry) = world.entry_mut(target) {
             if let Ok(stats) = entry.get_component_mut::<CombatStats>() {
-                if stats.max_hp == stats.hp {
+                if raise_max_hp && stats.max_hp == stats.hp {
                     stats.max_hp += 1 + amount / 8;
                     stats.hp = stats.max_hp;
                 } else {
@@ -50,14 +76,18 @@ pub(crate) fn output_die(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[system(for_each)]
 pub(crate) fn delete_the_dead(
     entity: &Entity,
     stats: &mut CombatStats,
     pos: &Position,
+    last_hit_by: Option<&LastHitBy>,
+    experience_value: Option<&ExperienceValue>,
     cb: &mut CommandBuffer,
     #[resource] player_entity: &PlayerEntity,
     #[resource] map: &mut Map,
+    #[resource] gain_experience_queue: &GainExperienceQueue,
 ) {
     if stats.hp < 1 && player_entity.0 != *entity {
         let idx = map.pos_to_idx(pos.0.into());
@@ -65,6 +95,41 @@ pub(crate) fn delete_the_dead(
         map.blocked[idx] = false;
         map.dangerous[idx] = false;
         cb.remove(*entity);
+
+        if let (Some(last_hit_by), Some(experience_value)) = (last_hit_by, experience_value) {
+            if last_hit_by.by_player {
+                gain_experience_queue.send(GainExperienceMessage {
+                    who: player_entity.0,
+                    amount: experience_value.xp,
+                });
+            }
+        }
+    }
+}
+
+/// The dead drop what they wielded and wore.
+#[system]
+#[read_component(CombatStats)]
+#[read_component(Position)]
+#[read_component(Equipped)]
+pub(crate) fn drop_equipment_of_the_dead(
+    world: &SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] player_entity: &PlayerEntity,
+) {
+    let dead: HashMap<Entity, Position> = <(Entity, &CombatStats, &Position)>::query()
+        .iter(world)
+        .filter(|(entity, stats, _)| stats.hp < 1 && **entity != player_entity.0)
+        .map(|(entity, _, pos)| (*entity, *pos))
+        .collect();
+    if dead.is_empty() {
+        return;
+    }
+    for (item, equipped) in <(Entity, &Equipped)>::query().iter(world) {
+        if let Some(pos) = dead.get(&equipped.owner) {
+            cb.remove_component::<Equipped>(*item);
+            cb.add_component(*item, *pos);
+        }
     }
 }
 
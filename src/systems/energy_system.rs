@@ -1,10 +1,11 @@
-use crate::components::Energy;
-use crate::resources::Time;
 use crate::RunState;
+use crate::components::{Energy, StatusEffects};
+use crate::resources::Time;
 use ::legion::*;
 use legion::world::SubWorld;
 
 #[system]
+#[read_component(StatusEffects)]
 #[write_component(Energy)]
 pub(crate) fn regain_energy(
     world: &mut SubWorld,
@@ -15,25 +16,25 @@ pub(crate) fn regain_energy(
         return;
     }
 
-    let mut max = i32::MIN;
-
-    // Find highest energy below zero.
-    for energy in <&Energy>::query().iter(world) {
-        if energy.energy < 0 && energy.energy > max {
-            max = energy.energy;
+    // Find how long it takes until the first one below zero reaches zero.
+    let mut wait = i32::MAX;
+    for (energy, effects) in <(&Energy, Option<&StatusEffects>)>::query().iter(world) {
+        if energy.energy < 0 {
+            let speed = super::speed(effects);
+            wait = wait.min((-energy.energy * 100 + speed - 1) / speed);
         }
     }
 
-    // TODO: Put a cap on max to make turn based animations smoother
+    // TODO: Put a cap on wait to make turn based animations smoother
     // or only when a particle has been spawned?
 
-    let max = max;
-    if max > i32::MIN {
-        for energy in <&mut Energy>::query().iter_mut(world) {
+    let wait = wait;
+    if wait < i32::MAX {
+        for (energy, effects) in <(&mut Energy, Option<&StatusEffects>)>::query().iter_mut(world) {
             if energy.energy < 0 {
-                energy.energy += -max;
+                energy.energy += wait * super::speed(effects) / 100;
             }
         }
-        time.tick += -max as i64;
+        time.tick += wait as i64;
     }
 }
@@ -0,0 +1,66 @@
+use crate::components::{Attributes, CombatStats, Experience};
+use crate::messages::GainExperienceMessage;
+use crate::queues::GainExperienceQueue;
+use crate::resources::OutputQueue;
+use bracket_lib::prelude::YELLOW;
+use legion::systems::CommandBuffer;
+use legion::world::SubWorld;
+use legion::*;
+
+/// The hit points gained at each level, before the constitution bonus.
+const HP_PER_LEVEL: i32 = 6;
+/// The attribute points to spend at each level.
+const POINTS_PER_LEVEL: i32 = 2;
+
+/// Awards the xp, and levels up those that have enough.
+#[system]
+#[read_component(Attributes)]
+#[write_component(Experience)]
+#[write_component(CombatStats)]
+pub(crate) fn experience(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] gain_experience_queue: &GainExperienceQueue,
+    #[resource] output: &OutputQueue,
+) {
+    for GainExperienceMessage { who, amount } in gain_experience_queue.try_iter() {
+        let Ok(mut entry) = world.entry_mut(who) else {
+            continue;
+        };
+        let constitution = entry
+            .get_component::<Attributes>()
+            .map_or(0, |attributes| Attributes::bonus(attributes.constitution));
+        let mut experience = entry
+            .get_component::<Experience>()
+            .cloned()
+            .unwrap_or_default();
+
+        experience.xp += amount;
+        output
+            .the(who)
+            .v(who, "gain")
+            .string(format!("{amount} xp"));
+
+        let mut hp_gain = 0;
+        while experience.xp >= experience.next_level_xp() {
+            experience.level += 1;
+            experience.unspent_points += POINTS_PER_LEVEL;
+            hp_gain += i32::max(1, HP_PER_LEVEL + constitution);
+            output
+                .color(YELLOW)
+                .the(who)
+                .v(who, "reach")
+                .string(format!("level {}!", experience.level));
+        }
+        if let Ok(stats) = entry.get_component_mut::<CombatStats>() {
+            stats.max_hp += hp_gain;
+            stats.hp += hp_gain;
+        }
+
+        match entry.get_component_mut::<Experience>() {
+            Ok(current) => *current = experience,
+            // Those from older saves start out now.
+            Err(_) => cb.add_component(who, experience),
+        }
+    }
+}
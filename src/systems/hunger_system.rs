@@ -0,0 +1,66 @@
+use crate::components::{Energy, HungerClock, HungerState};
+use crate::messages::{EatFoodMessage, SufferDamageMessage};
+use crate::queues::{EatFoodQueue, SufferDamageQueue};
+use crate::resources::{OutputQueue, Time};
+use bracket_lib::prelude::RandomNumberGenerator;
+use legion::world::SubWorld;
+use legion::*;
+
+/// Resets the hunger clocks of those who ate, and moves the others on to
+/// hungrier states as time passes. Those fainting from hunger lose hit
+/// points and sometimes a turn.
+#[system]
+#[write_component(HungerClock)]
+#[write_component(Energy)]
+pub(crate) fn hunger(
+    world: &mut SubWorld,
+    #[resource] eat_food_queue: &EatFoodQueue,
+    #[resource] suffer_damage_queue: &SufferDamageQueue,
+    #[resource] output: &OutputQueue,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] time: &Time,
+) {
+    for EatFoodMessage { who } in eat_food_queue.try_iter() {
+        if let Ok(mut entry) = world.entry_mut(who) {
+            if let Ok(clock) = entry.get_component_mut::<HungerClock>() {
+                *clock = HungerClock::well_fed(time.tick);
+                output.the(who).is(who).s("well fed");
+            }
+        }
+    }
+
+    for (entity, clock, energy) in
+        <(Entity, &mut HungerClock, &mut Energy)>::query().iter_mut(world)
+    {
+        while clock.end_tick <= time.tick {
+            if clock.state == HungerState::Fainting {
+                clock.end_tick += clock.state.duration();
+                suffer_damage_queue.send(SufferDamageMessage {
+                    target: *entity,
+                    amount: 1,
+                    attacker: None,
+                });
+                if rng.roll_dice(1, 6) == 1 {
+                    energy.energy -= 100;
+                    output.the(*entity).v(*entity, "faint").s("from hunger");
+                }
+                continue;
+            }
+
+            clock.state = match clock.state {
+                HungerState::WellFed => HungerState::Normal,
+                HungerState::Normal => HungerState::Hungry,
+                HungerState::Hungry => HungerState::Weak,
+                HungerState::Weak | HungerState::Fainting => HungerState::Fainting,
+            };
+            clock.end_tick += clock.state.duration();
+            let output = output.the(*entity).is(*entity);
+            match clock.state {
+                HungerState::Normal => output.s("no longer well fed"),
+                HungerState::Hungry => output.s("hungry"),
+                HungerState::Weak => output.s("weak with hunger"),
+                _ => output.s("fainting from hunger!"),
+            };
+        }
+    }
+}
@@ -1,14 +1,99 @@
-use crate::components::{Energy, InBackpack, ItemIndex, Position};
-use crate::messages::{WantsToDropMessage, WantsToPickupMessage};
-use crate::queues::{WantsToDropQueue, WantsToPickupQueue};
+use crate::components::{
+    Energy, EquipmentSlot, Equippable, Equipped, InBackpack, Item, ItemIndex, Name, Position,
+    Quantity, Renderable,
+};
+use crate::messages::{
+    RemoveItemMessage, WantsToDropMessage, WantsToEquipMessage, WantsToPickupMessage,
+    WantsToUnequipMessage,
+};
+use crate::queues::{
+    RemoveItemQueue, WantsToDropQueue, WantsToEquipQueue, WantsToPickupQueue, WantsToUnequipQueue,
+};
 use crate::resources::{OutputQueue, PlayerEntity, PlayerPosition};
 use ::bracket_lib::prelude::YELLOW;
+use ::legion::query::component;
 use ::legion::systems::CommandBuffer;
 use ::legion::world::SubWorld;
 use ::legion::*;
 
+/// The energy it takes to pick up an item.
+const PICKUP_ENERGY: i32 = 90;
+/// And each item after it, when picking up several at once.
+const EXTRA_PICKUP_ENERGY: i32 = 20;
+/// However many there are.
+const MAX_PICKUP_ENERGY: i32 = 300;
+
+/// Splits `count` off the item's stack into a new item, or returns the item
+/// itself when that is all of it. The new item isn't anywhere, and only
+/// exists once the command buffer is flushed.
+///
+/// The new item gets the stack's name and looks, and the other components
+/// of the item's definition.
+pub(crate) fn split_stack(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    item: Entity,
+    count: i32,
+) -> Entity {
+    debug_assert!(count > 0, "Can't split {count} off a stack");
+    let Ok(mut entry) = world.entry_mut(item) else {
+        return item;
+    };
+    let Ok(quantity) = entry.get_component_mut::<Quantity>() else {
+        return item;
+    };
+    if quantity.count <= count {
+        return item;
+    }
+    quantity.count -= count;
+    let name = entry.get_component::<Name>().unwrap().clone();
+    let renderable = entry.get_component::<Renderable>().unwrap().clone();
+    let def = crate::raws::get().item(&name.name);
+    debug_assert!(def.is_some(), "{} has no item definition", name.name);
+
+    let split = cb.push((renderable, name, Item {}, Quantity { count }));
+    if let Some(def) = def {
+        cb.exec_mut(move |world, _| {
+            if let Some(mut entry) = world.entry(split) {
+                crate::spawner::add_item_components(&mut entry, def);
+            }
+        });
+    }
+    split
+}
+
+/// The name the item stacks by, if it is stackable.
+fn stack_name(world: &SubWorld, item: Entity) -> Option<String> {
+    let entry = world.entry_ref(item).ok()?;
+    entry.get_component::<Quantity>().ok()?;
+    Some(entry.get_component::<Name>().ok()?.name.clone())
+}
+
+/// The stack in the backpack that the item would join, if it is stackable.
+fn stack_for(world: &SubWorld, who: Entity, item: Entity) -> Option<Entity> {
+    let name = stack_name(world, item)?;
+    <(Entity, &Name, &InBackpack)>::query()
+        .filter(component::<Quantity>() & !component::<Equipped>())
+        .iter(world)
+        .find(|(entity, stack_name, in_backpack)| {
+            **entity != item && in_backpack.owner == who && stack_name.name == name
+        })
+        .map(|(entity, _, _)| *entity)
+}
+
+/// The energy it takes to pick up that many items at once.
+fn pickup_energy(count: i32) -> i32 {
+    i32::min(
+        MAX_PICKUP_ENERGY,
+        PICKUP_ENERGY + EXTRA_PICKUP_ENERGY * (count - 1),
+    )
+}
+
 #[system]
+#[read_component(Name)]
+#[read_component(Renderable)]
 #[write_component(Energy)]
+#[write_component(Quantity)]
 pub(crate) fn drop(
     world: &mut SubWorld,
     cb: &mut CommandBuffer,
@@ -23,11 +108,17 @@ pub(crate) fn drop(
     for WantsToDropMessage {
         who: dropper_entity,
         item,
+        count,
     } in wants_to_drop_queue.try_iter()
     {
+        let item = match count {
+            Some(count) => split_stack(world, cb, item, count),
+            None => item,
+        };
         // TODO: Use Dropper's position.
         cb.add_component(item, Position(player_position));
         cb.remove_component::<InBackpack>(item);
+        cb.remove_component::<Equipped>(item);
         if dropper_entity == player_entity {
             output
                 .the(dropper_entity)
@@ -40,28 +131,85 @@ pub(crate) fn drop(
 }
 
 #[system]
+#[read_component(Equipped)]
+#[read_component(Name)]
 #[write_component(Energy)]
 #[write_component(InBackpack)]
 #[write_component(ItemIndex)]
+#[write_component(Quantity)]
 pub(crate) fn pickup(
     world: &mut SubWorld,
     cb: &mut CommandBuffer,
     #[resource] player_entity: &PlayerEntity,
     #[resource] wants_to_pickup_queue: &mut WantsToPickupQueue,
+    #[resource] remove_item_queue: &RemoveItemQueue,
     #[resource] output: &OutputQueue,
 ) {
     let player_entity = player_entity.0;
+    let mut picked_up = std::collections::HashMap::new();
+    // Letters given out in this run, their components are not added yet.
+    let mut given_indexes = std::collections::HashSet::new();
+    // Stacks picked up in this run, with who got them, their name and letter.
+    let mut new_stacks: Vec<(Entity, String, Entity, Option<u8>)> = vec![];
 
     for WantsToPickupMessage {
         who: who_entity,
         item: item_entity,
     } in wants_to_pickup_queue.try_iter()
     {
+        let stack = stack_for(world, who_entity, item_entity)
+            .map(|stack| {
+                let index = world.entry_ref(stack).ok().and_then(|entry| {
+                    entry
+                        .get_component::<ItemIndex>()
+                        .ok()
+                        .map(|item_index| item_index.index)
+                });
+                (stack, index)
+            })
+            .or_else(|| {
+                let name = stack_name(world, item_entity)?;
+                new_stacks
+                    .iter()
+                    .find(|(who, stack_name, stack, _)| {
+                        *who == who_entity && *stack_name == name && *stack != item_entity
+                    })
+                    .map(|(_, _, stack, index)| (*stack, *index))
+            });
+        if let Some((stack, index)) = stack {
+            // It joins the stack, and is deleted once it has been told of.
+            let count = world
+                .entry_ref(item_entity)
+                .map_or(1, |entry| entry.get_component::<Quantity>().unwrap().count);
+            let mut stack_entry = world.entry_mut(stack).unwrap();
+            stack_entry.get_component_mut::<Quantity>().unwrap().count += count;
+            let output = output
+                .the(who_entity)
+                .v(who_entity, "pick")
+                .s("up")
+                .a(item_entity);
+            if let Some(index) = index {
+                output
+                    .color(YELLOW)
+                    .string(format!(" ({})", crate::gui::index_to_letter(index)));
+            }
+            cb.remove_component::<Position>(item_entity);
+            remove_item_queue.send(RemoveItemMessage {
+                target: item_entity,
+            });
+            *picked_up.entry(who_entity).or_default() += 1;
+            continue;
+        }
+
+        let mut index = None;
         if who_entity == player_entity {
             let mut possible_indexes = std::collections::HashSet::new();
             for c in 0..52 {
                 possible_indexes.insert(c);
             }
+            for idx in &given_indexes {
+                possible_indexes.remove(idx);
+            }
             for (item_idx, in_backpack) in <(&ItemIndex, &InBackpack)>::query().iter(world) {
                 if in_backpack.owner == player_entity {
                     possible_indexes.remove(&item_idx.index);
@@ -85,6 +233,8 @@ pub(crate) fn pickup(
                 idx = possible_indexes[0];
                 cb.add_component(item_entity, ItemIndex { index: idx });
             }
+            given_indexes.insert(idx);
+            index = Some(idx);
             output
                 .the(who_entity)
                 .v(who_entity, "pick")
@@ -102,8 +252,156 @@ pub(crate) fn pickup(
 
         cb.remove_component::<Position>(item_entity);
         cb.add_component(item_entity, InBackpack { owner: who_entity });
+        if let Some(name) = stack_name(world, item_entity) {
+            new_stacks.push((who_entity, name, item_entity, index));
+        }
 
+        *picked_up.entry(who_entity).or_default() += 1;
+    }
+
+    // Picking up a pile takes longer than picking up one item.
+    for (who_entity, count) in picked_up {
         let mut who_entity = world.entry_mut(who_entity).unwrap();
-        who_entity.get_component_mut::<Energy>().unwrap().energy = -90;
+        who_entity.get_component_mut::<Energy>().unwrap().energy = -pickup_energy(count);
+    }
+}
+
+#[system]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[write_component(Energy)]
+pub(crate) fn equip(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] wants_to_equip_queue: &mut WantsToEquipQueue,
+    #[resource] output: &OutputQueue,
+) {
+    for WantsToEquipMessage { who, item } in wants_to_equip_queue.try_iter() {
+        let Ok(item_entry) = world.entry_ref(item) else {
+            continue;
+        };
+        let Ok(slot) = item_entry.get_component::<Equippable>().map(|e| e.slot) else {
+            output.s("You can't wield or wear").the(item);
+            continue;
+        };
+        if item_entry.get_component::<Equipped>().is_ok() {
+            output.the(who).is(who).s("already using").my(who, item);
+            continue;
+        }
+
+        // Whatever is in the slot comes off first.
+        let in_slot: Vec<Entity> = <(Entity, &Equipped)>::query()
+            .iter(world)
+            .filter(|(_, equipped)| equipped.owner == who && equipped.slot == slot)
+            .map(|(entity, _)| *entity)
+            .collect();
+        for old_item in in_slot {
+            cb.remove_component::<Equipped>(old_item);
+            output_unequip(output, who, old_item, slot);
+        }
+
+        cb.add_component(item, Equipped { owner: who, slot });
+        if slot.is_wielded() {
+            output.the(who).v(who, "wield").my(who, item);
+        } else {
+            output.the(who).v(who, "put").s("on").my(who, item);
+        }
+        let mut who_entity = world.entry_mut(who).unwrap();
+        who_entity.get_component_mut::<Energy>().unwrap().energy = -100;
+    }
+}
+
+#[system]
+#[read_component(Equipped)]
+#[write_component(Energy)]
+pub(crate) fn unequip(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] wants_to_unequip_queue: &mut WantsToUnequipQueue,
+    #[resource] output: &OutputQueue,
+) {
+    for WantsToUnequipMessage { who, item } in wants_to_unequip_queue.try_iter() {
+        let slot = world
+            .entry_ref(item)
+            .ok()
+            .and_then(|entry| entry.get_component::<Equipped>().ok().map(|e| e.slot));
+        let Some(slot) = slot else {
+            continue;
+        };
+        cb.remove_component::<Equipped>(item);
+        output_unequip(output, who, item, slot);
+        let mut who_entity = world.entry_mut(who).unwrap();
+        who_entity.get_component_mut::<Energy>().unwrap().energy = -100;
+    }
+}
+
+fn output_unequip(output: &OutputQueue, who: Entity, item: Entity, slot: EquipmentSlot) {
+    if slot.is_wielded() {
+        output.the(who).v(who, "put").s("away").my(who, item);
+    } else {
+        output.the(who).v(who, "take").s("off").my(who, item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::positions::MapPosition;
+    use ::std::collections::VecDeque;
+    use ::std::sync::Mutex;
+
+    #[test]
+    fn piles_cost_less_per_item() {
+        assert_eq!(pickup_energy(1), PICKUP_ENERGY);
+        assert_eq!(pickup_energy(2), PICKUP_ENERGY + EXTRA_PICKUP_ENERGY);
+        assert_eq!(pickup_energy(20), MAX_PICKUP_ENERGY);
+    }
+
+    #[test]
+    fn stacks_picked_up_together_merge() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        crate::queues::register_queues(&mut resources);
+
+        let player = world.push((Energy { energy: 0 },));
+        let pos = Position(MapPosition { x: 1, y: 1 });
+        let arrows = |count| {
+            (
+                Name {
+                    name: "arrow".to_string(),
+                    proper_name: false,
+                },
+                Item {},
+                Quantity { count },
+                pos,
+            )
+        };
+        let items = [world.push(arrows(3)), world.push(arrows(2))];
+        for item in items {
+            resources
+                .get::<WantsToPickupQueue>()
+                .unwrap()
+                .send(WantsToPickupMessage { who: player, item });
+        }
+        resources.insert(PlayerEntity(player));
+        resources.insert(OutputQueue::new(Mutex::new(VecDeque::new()), player));
+
+        Schedule::builder()
+            .add_system(pickup_system())
+            .build()
+            .execute(&mut world, &mut resources);
+
+        let stacks: Vec<_> = <(&Quantity, &InBackpack, &ItemIndex)>::query()
+            .iter(&world)
+            .map(|(quantity, _, item_index)| (quantity.count, item_index.index))
+            .collect();
+        assert_eq!(stacks, vec![(5, 0)]);
+        let energy = world
+            .entry(player)
+            .unwrap()
+            .get_component::<Energy>()
+            .unwrap()
+            .energy;
+        assert_eq!(energy, -pickup_energy(2));
     }
 }
@@ -1,5 +1,6 @@
-use crate::components::{BlocksTile, Monster, Position};
+use crate::components::{BlocksTile, Hidden, Monster, Position, Trap};
 use crate::resources::Map;
+use legion::query::component;
 use legion::{system, Entity};
 use std::option::Option;
 
@@ -22,3 +23,10 @@ pub(crate) fn map_indexing(
     map.tile_content[idx].push(*entity);
     map.dangerous[idx] = monster.is_some();
 }
+
+#[system(for_each)]
+#[filter(!component::<Hidden>())]
+pub(crate) fn map_indexing_traps(_trap: &Trap, pos: &Position, #[resource] map: &mut Map) {
+    let idx = map.pos_to_idx(*pos);
+    map.known_traps[idx] = true;
+}
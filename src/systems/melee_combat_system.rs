@@ -1,59 +1,189 @@
-use crate::components::{CombatStats, Energy};
-use crate::messages::{SufferDamageMessage, WantsToMeleeMessage};
-use crate::queues::{SufferDamageQueue, WantsToMeleeQueue};
+use crate::components::{
+    Attributes, CombatStats, DefenseBonus, Energy, EquipmentSlot, Equipped, HungerClock,
+    MeleeDamage, MeleePowerBonus, Position, Resistance, Resistances,
+};
+use crate::messages::{ParticleMessage, SufferDamageMessage, WantsToMeleeMessage};
+use crate::queues::{ParticleQueue, SufferDamageQueue, WantsToMeleeQueue};
 use crate::resources::OutputQueue;
+use bracket_lib::prelude::{RED, RGB, RandomNumberGenerator};
 use legion::world::SubWorld;
 use legion::*;
 
+/// What the attack roll plus the attacker's power must reach, plus the
+/// target's defense, to hit.
+pub(super) const BASE_DEFENSE: i32 = 10;
+
+/// What the owner wields and wears adds to their power and defense.
+pub(super) struct Equipment {
+    pub power: i32,
+    pub defense: i32,
+    weapon: Option<Entity>,
+    weapon_damage: Option<MeleeDamage>,
+}
+
+pub(super) fn equipment(world: &SubWorld, owner: Entity) -> Equipment {
+    let mut equipment = Equipment {
+        power: 0,
+        defense: 0,
+        weapon: None,
+        weapon_damage: None,
+    };
+    let mut query = <(
+        Entity,
+        &Equipped,
+        Option<&MeleePowerBonus>,
+        Option<&DefenseBonus>,
+        Option<&MeleeDamage>,
+    )>::query();
+    for (item, equipped, power_bonus, defense_bonus, damage) in query.iter(world) {
+        if equipped.owner != owner {
+            continue;
+        }
+        if equipped.slot == EquipmentSlot::Weapon {
+            equipment.weapon = Some(*item);
+            equipment.weapon_damage = damage.cloned();
+        }
+        equipment.power += power_bonus.map_or(0, |bonus| bonus.power);
+        equipment.defense += defense_bonus.map_or(0, |bonus| bonus.defense);
+    }
+    equipment
+}
+
 #[system]
+#[read_component(Attributes)]
 #[read_component(CombatStats)]
+#[read_component(Equipped)]
+#[read_component(HungerClock)]
+#[read_component(MeleePowerBonus)]
+#[read_component(DefenseBonus)]
+#[read_component(MeleeDamage)]
+#[read_component(Position)]
+#[read_component(Resistances)]
 #[write_component(Energy)]
 pub(crate) fn melee_combat(
     world: &mut SubWorld,
     #[resource] suffer_damage_queue: &SufferDamageQueue,
+    #[resource] particle_queue: &ParticleQueue,
     #[resource] wants_to_melee_queue: &mut WantsToMeleeQueue,
     #[resource] output: &OutputQueue,
+    #[resource] rng: &mut RandomNumberGenerator,
 ) {
     for WantsToMeleeMessage {
         attacker: attacker_entity,
         target: melee_target_entity,
     } in wants_to_melee_queue.try_iter()
     {
-        if let Ok(attacker_entry) = world.entry_ref(attacker_entity) {
-            let attacker_power = attacker_entry.get_component::<CombatStats>().unwrap().power;
-            let target = world.entry_ref(melee_target_entity);
-            match target { Ok(target) => {
-                let target_stats = target.get_component::<CombatStats>().unwrap();
+        let Ok(attacker_entry) = world.entry_ref(attacker_entity) else {
+            continue;
+        };
+        let attacker_equipment = equipment(world, attacker_entity);
+        let strength = attacker_entry
+            .get_component::<Attributes>()
+            .map_or(0, |attributes| Attributes::bonus(attributes.strength));
+        let hunger = attacker_entry
+            .get_component::<HungerClock>()
+            .map_or(0, |clock| clock.state.power_modifier());
+        let attacker_power = attacker_entry.get_component::<CombatStats>().unwrap().power
+            + attacker_equipment.power
+            + strength
+            + hunger;
+        let melee_damage = attacker_equipment
+            .weapon_damage
+            .or_else(|| attacker_entry.get_component::<MeleeDamage>().ok().cloned())
+            .unwrap_or(MeleeDamage::UNARMED);
 
+        match world.entry_ref(melee_target_entity) {
+            Ok(target) => {
+                let target_stats = target.get_component::<CombatStats>().unwrap();
                 if target_stats.hp > 0 {
-                    let damage = i32::max(0, attacker_power - target_stats.defense);
+                    let dexterity = target
+                        .get_component::<Attributes>()
+                        .map_or(0, |attributes| Attributes::bonus(attributes.dexterity));
+                    let target_defense = target_stats.defense
+                        + equipment(world, melee_target_entity).defense
+                        + dexterity;
+                    let resistance = target
+                        .get_component::<Resistances>()
+                        .ok()
+                        .and_then(|r| r.resistances.get(&melee_damage.damage_type).copied());
 
-                    if damage == 0 {
+                    let roll = rng.roll_dice(1, 20);
+                    if roll == 1 {
                         output
                             .the(attacker_entity)
-                            .is(attacker_entity)
-                            .s("unable to hurt")
-                            .the(melee_target_entity);
-                    } else {
+                            .v(attacker_entity, "stumble")
+                            .s("and")
+                            .v(attacker_entity, "miss")
+                            .the(melee_target_entity)
+                            .s("completely");
+                    } else if roll != 20 && roll + attacker_power < BASE_DEFENSE + target_defense {
                         output
                             .the(attacker_entity)
-                            .v(attacker_entity, "hit")
-                            .the(melee_target_entity)
-                            .string(format!(", for {damage} hp"));
-                        suffer_damage_queue.send(SufferDamageMessage {
-                            target: melee_target_entity,
-                            amount: damage,
-                        });
+                            .v(attacker_entity, "miss")
+                            .the(melee_target_entity);
+                    } else {
+                        let critical = roll == 20;
+                        let mut dice = melee_damage.dice;
+                        if critical {
+                            dice.count *= 2;
+                        }
+                        let damage = (dice.roll(rng) + strength).max(0);
+                        let damage = resistance.map_or(damage, |r| r.apply(damage));
+                        if let Ok(pos) = target.get_component::<Position>() {
+                            particle_queue.send(ParticleMessage::Flash {
+                                pos: pos.0,
+                                fg: RGB::named(RED),
+                            });
+                        }
+
+                        let output = match attacker_equipment.weapon {
+                            Some(weapon) => output
+                                .the(attacker_entity)
+                                .v(attacker_entity, "swing")
+                                .my(attacker_entity, weapon)
+                                .s("at"),
+                            None => output.the(attacker_entity).v(attacker_entity, "hit"),
+                        };
+                        let output = output.the(melee_target_entity);
+                        let output = if critical {
+                            output.s(", a critical hit")
+                        } else {
+                            output
+                        };
+                        if damage == 0 {
+                            output
+                                .s(", but")
+                                .the(melee_target_entity)
+                                .is(melee_target_entity)
+                                .s("unharmed");
+                        } else {
+                            output.string(damage_text(damage, resistance));
+                            suffer_damage_queue.send(SufferDamageMessage {
+                                target: melee_target_entity,
+                                amount: damage,
+                                attacker: Some(attacker_entity),
+                            });
+                        }
                     }
                 }
-            } _ => {
+            }
+            _ => {
                 output
                     .the(attacker_entity)
                     .v(attacker_entity, "want")
                     .s("to attak a ghost?");
-            }}
-            let mut entry = world.entry_mut(attacker_entity).unwrap();
-            entry.get_component_mut::<Energy>().unwrap().energy = -120;
+            }
         }
+        let mut entry = world.entry_mut(attacker_entity).unwrap();
+        entry.get_component_mut::<Energy>().unwrap().energy = -120;
+    }
+}
+
+/// How much it hurt, and whether the resistance made a difference.
+pub(super) fn damage_text(damage: i32, resistance: Option<Resistance>) -> String {
+    match resistance {
+        Some(Resistance::Vulnerable) => format!(", for a painful {damage} hp"),
+        Some(Resistance::Resistant) => format!(", for only {damage} hp"),
+        _ => format!(", for {damage} hp"),
     }
 }
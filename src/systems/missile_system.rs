@@ -0,0 +1,168 @@
+use super::inventory_system::split_stack;
+use super::melee_combat_system::{BASE_DEFENSE, damage_text, equipment};
+use crate::components::{
+    Attributes, CombatStats, DefenseBonus, Energy, Equipped, HungerClock, InBackpack,
+    MeleePowerBonus, MissileDamage, Name, Position, Quantity, Renderable, Resistances,
+};
+use crate::messages::{ParticleMessage, SufferDamageMessage, WantsToThrowMessage};
+use crate::queues::{ParticleQueue, SufferDamageQueue, WantsToThrowQueue};
+use crate::resources::{Map, OutputQueue};
+use bracket_lib::prelude::{RED, RGB, RandomNumberGenerator};
+use legion::systems::CommandBuffer;
+use legion::world::SubWorld;
+use legion::*;
+
+/// How far things can be thrown by hand.
+pub(crate) const THROW_RANGE: i32 = 6;
+
+/// Throws or fires the items along the line of fire. They hit the first one
+/// in the way, rolling to hit like in melee with dexterity instead of
+/// strength, and stay on the floor where they land.
+#[allow(clippy::too_many_arguments)]
+#[system]
+#[read_component(Attributes)]
+#[read_component(CombatStats)]
+#[read_component(DefenseBonus)]
+#[read_component(Equipped)]
+#[read_component(HungerClock)]
+#[read_component(MeleePowerBonus)]
+#[read_component(MissileDamage)]
+#[read_component(Name)]
+#[read_component(Position)]
+#[read_component(Renderable)]
+#[read_component(Resistances)]
+#[write_component(Energy)]
+#[write_component(Quantity)]
+pub(crate) fn missiles(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] wants_to_throw_queue: &mut WantsToThrowQueue,
+    #[resource] suffer_damage_queue: &SufferDamageQueue,
+    #[resource] particle_queue: &ParticleQueue,
+    #[resource] map: &Map,
+    #[resource] output: &OutputQueue,
+    #[resource] rng: &mut RandomNumberGenerator,
+) {
+    for WantsToThrowMessage {
+        who,
+        item,
+        target,
+        launcher,
+    } in wants_to_throw_queue.try_iter()
+    {
+        let from = world
+            .entry_ref(who)
+            .ok()
+            .and_then(|entry| entry.get_component::<Position>().ok().map(|pos| pos.0));
+        let Some(from) = from else {
+            continue;
+        };
+        // Only one of a stack flies, the item's components are read from
+        // the stack as the new one doesn't exist yet.
+        let thrown = split_stack(world, cb, item, 1);
+        let (Ok(who_entry), Ok(item_entry)) = (world.entry_ref(who), world.entry_ref(item)) else {
+            continue;
+        };
+        let dexterity = who_entry
+            .get_component::<Attributes>()
+            .map_or(0, |attributes| Attributes::bonus(attributes.dexterity));
+        let hunger = who_entry
+            .get_component::<HungerClock>()
+            .map_or(0, |clock| clock.state.power_modifier());
+        // The wielded weapon doesn't help, only the launcher if there is one.
+        let launcher_power = launcher
+            .and_then(|launcher| world.entry_ref(launcher).ok())
+            .and_then(|entry| entry.get_component::<MeleePowerBonus>().ok().cloned())
+            .map_or(0, |bonus| bonus.power);
+        let attacker_power = who_entry
+            .get_component::<CombatStats>()
+            .map_or(0, |stats| stats.power)
+            + launcher_power
+            + dexterity
+            + hunger;
+        let missile_damage = item_entry
+            .get_component::<MissileDamage>()
+            .ok()
+            .cloned()
+            .unwrap_or(MissileDamage::IMPROVISED);
+
+        match launcher {
+            Some(launcher) => output
+                .the(who)
+                .v(who, "fire")
+                .the(thrown)
+                .s("from")
+                .my(who, launcher),
+            None => output.the(who).v(who, "throw").the(thrown),
+        };
+
+        let path = map.line_of_fire(from, target);
+        let landing = path.last().copied().unwrap_or(from);
+        let victim = path
+            .last()
+            .and_then(|pos| map.tile_content[map.map_pos_to_idx(*pos)].first().copied());
+        if let Ok(renderable) = item_entry.get_component::<Renderable>() {
+            particle_queue.send(ParticleMessage::Missile {
+                path,
+                glyph: renderable.glyph,
+                fg: renderable.fg,
+            });
+        }
+        if let Some(victim) = victim {
+            let victim_entry = world.entry_ref(victim).unwrap();
+            if let Ok(victim_stats) = victim_entry.get_component::<CombatStats>() {
+                let victim_dexterity = victim_entry
+                    .get_component::<Attributes>()
+                    .map_or(0, |attributes| Attributes::bonus(attributes.dexterity));
+                let victim_defense =
+                    victim_stats.defense + equipment(world, victim).defense + victim_dexterity;
+                let resistance = victim_entry
+                    .get_component::<Resistances>()
+                    .ok()
+                    .and_then(|r| r.resistances.get(&missile_damage.damage_type).copied());
+
+                let roll = rng.roll_dice(1, 20);
+                if roll == 1
+                    || (roll != 20 && roll + attacker_power < BASE_DEFENSE + victim_defense)
+                {
+                    output.the(thrown).v(thrown, "miss").the(victim);
+                } else {
+                    let critical = roll == 20;
+                    let mut dice = missile_damage.dice;
+                    if critical {
+                        dice.count *= 2;
+                    }
+                    let damage = dice.roll(rng).max(0);
+                    let damage = resistance.map_or(damage, |r| r.apply(damage));
+                    particle_queue.send(ParticleMessage::Flash {
+                        pos: landing,
+                        fg: RGB::named(RED),
+                    });
+
+                    let output = output.the(thrown).v(thrown, "hit").the(victim);
+                    let output = if critical {
+                        output.s(", a critical hit")
+                    } else {
+                        output
+                    };
+                    if damage == 0 {
+                        output.s(", but").the(victim).is(victim).s("unharmed");
+                    } else {
+                        output.string(damage_text(damage, resistance));
+                        suffer_damage_queue.send(SufferDamageMessage {
+                            target: victim,
+                            amount: damage,
+                            attacker: Some(who),
+                        });
+                    }
+                }
+            }
+        }
+
+        cb.remove_component::<InBackpack>(thrown);
+        cb.remove_component::<Equipped>(thrown);
+        cb.add_component(thrown, Position(landing));
+        let mut who_entity = world.entry_mut(who).unwrap();
+        who_entity.get_component_mut::<Energy>().unwrap().energy = -100;
+    }
+}
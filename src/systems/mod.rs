@@ -2,20 +2,34 @@ mod camera_system;
 mod consume_system;
 mod damage_system;
 mod energy_system;
+mod experience_system;
+mod hunger_system;
 mod inventory_system;
 mod map_indexing_system;
 mod melee_combat_system;
+mod missile_system;
 mod monster_ai_systems;
 mod output_system;
+mod particle_system;
+mod regeneration_system;
+mod status_effect_system;
+mod trap_system;
 mod visibility_system;
 
 pub(crate) use camera_system::*;
 pub(crate) use consume_system::*;
 pub(crate) use damage_system::*;
 pub(crate) use energy_system::*;
+pub(crate) use experience_system::*;
+pub(crate) use hunger_system::*;
 pub(crate) use inventory_system::*;
 pub(crate) use map_indexing_system::*;
 pub(crate) use melee_combat_system::*;
+pub(crate) use missile_system::*;
 pub(crate) use monster_ai_systems::*;
 pub(crate) use output_system::*;
+pub(crate) use particle_system::*;
+pub(crate) use regeneration_system::*;
+pub(crate) use status_effect_system::*;
+pub(crate) use trap_system::*;
 pub(crate) use visibility_system::*;
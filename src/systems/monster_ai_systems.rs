@@ -1,23 +1,30 @@
-use crate::components::{Energy, Monster, Position, Viewshed};
-use crate::messages::WantsToMeleeMessage;
-use crate::queues::WantsToMeleeQueue;
+use crate::components::{Alerted, Energy, Monster, Position, StatusEffects, Viewshed};
+use crate::messages::{EnteredTileMessage, WantsToMeleeMessage};
+use crate::positions::{Direction, MapPosition};
+use crate::queues::{EnteredTileQueue, WantsToMeleeQueue};
 use crate::resources::{Map, PlayerEntity, PlayerPosition};
 use crate::RunState;
 use bracket_lib::prelude::*;
-use legion::{system, world::SubWorld, Entity, IntoQuery};
+use legion::{system, systems::CommandBuffer, world::SubWorld, Entity, IntoQuery};
 
+#[allow(clippy::too_many_arguments)]
 #[system]
 #[read_component(Monster)]
+#[read_component(Alerted)]
+#[read_component(StatusEffects)]
 #[write_component(Viewshed)]
 #[write_component(Position)]
 #[write_component(Energy)]
 pub(crate) fn monster_ai(
     world: &mut SubWorld,
+    commands: &mut CommandBuffer,
     #[resource] rs: &RunState,
     #[resource] map: &mut Map,
     #[resource] player_pos: &mut PlayerPosition,
     #[resource] player_entity: &mut PlayerEntity,
     #[resource] wants_to_melee_queue: &WantsToMeleeQueue,
+    #[resource] entered_tile_queue: &EnteredTileQueue,
+    #[resource] rng: &mut RandomNumberGenerator,
 ) {
     if *rs != RunState::Tick && *rs != RunState::EnergylessTick {
         return;
@@ -26,15 +33,50 @@ pub(crate) fn monster_ai(
     let player_pos = player_pos.0;
     let player_entity = player_entity.0;
 
-    let mut ready: Vec<_> = <(Entity, &mut Viewshed, &mut Position, &mut Energy)>::query()
-        .filter(legion::query::component::<Monster>())
-        .iter_mut(world)
-        .filter(|(_, _, _, energy)| energy.energy >= 0)
-        .collect();
+    let mut ready: Vec<_> = <(
+        Entity,
+        &mut Viewshed,
+        &mut Position,
+        &mut Energy,
+        Option<&Alerted>,
+        Option<&StatusEffects>,
+    )>::query()
+    .filter(legion::query::component::<Monster>())
+    .iter_mut(world)
+    .filter(|(_, _, _, energy, _, _)| energy.energy >= 0)
+    .collect();
 
-    ready.sort_by_key(|(_, _, _, energy)| -energy.energy);
+    ready.sort_by_key(|(_, _, _, energy, _, _)| -energy.energy);
+
+    let mut opened_door = false;
+
+    for (entity, viewshed, pos, energy, alerted, effects) in ready {
+        if super::stumbles(effects, rng) {
+            energy.energy = -100;
+            let (dx, dy) = Direction::random(rng).into();
+            let new_map_pos = MapPosition {
+                x: pos.0.x + dx,
+                y: pos.0.y + dy,
+            };
+            if map.in_bounds(new_map_pos.into()) {
+                let old_idx = map.pos_to_idx(*pos);
+                let new_idx = map.map_pos_to_idx(new_map_pos);
+                if !map.blocked[new_idx] && !map.is_closed_door(new_map_pos) {
+                    pos.0 = new_map_pos;
+                    map.blocked[old_idx] = false;
+                    map.blocked[new_idx] = true;
+                    map.dangerous[old_idx] = false;
+                    map.dangerous[new_idx] = true;
+                    viewshed.dirty = true;
+                    entered_tile_queue.send(EnteredTileMessage {
+                        who: *entity,
+                        pos: new_map_pos,
+                    });
+                }
+            }
+            continue;
+        }
 
-    for (entity, viewshed, pos, energy) in ready {
         let distance =
             DistanceAlg::Chebyshev.distance2d(Point::new(pos.0.x, pos.0.y), player_pos.into());
         if distance < 1.5 {
@@ -43,20 +85,47 @@ pub(crate) fn monster_ai(
                 attacker: *entity,
                 target: player_entity,
             });
-        } else if viewshed.visible_tiles.contains(&player_pos) {
+            continue;
+        }
+
+        // Chase the player when they are in sight, or else go to what was heard.
+        let target = if viewshed.visible_tiles.contains(&player_pos) {
+            if alerted.is_some() {
+                commands.remove_component::<Alerted>(*entity);
+            }
+            Some(player_pos)
+        } else if let Some(alerted) = alerted {
+            if alerted.pos == pos.0 {
+                commands.remove_component::<Alerted>(*entity);
+                None
+            } else {
+                Some(alerted.pos)
+            }
+        } else {
+            None
+        };
+
+        if let Some(target) = target {
             let path = a_star_search(
                 map.pos_to_idx(*pos) as i32,
-                map.map_pos_to_idx(player_pos) as i32,
+                map.map_pos_to_idx(target) as i32,
                 &*map,
             );
             if path.success && path.steps.len() > 1 {
                 // TODO: Move to some action system.
-                // Walk towards player:
+                // Walk towards the target:
                 energy.energy = -100;
                 let old_idx = map.pos_to_idx(*pos);
                 let new_idx = path.steps[1];
                 let new_pos = map.index_to_point2d(new_idx);
-                if !map.blocked[new_idx] {
+                let new_map_pos = MapPosition {
+                    x: new_pos.x,
+                    y: new_pos.y,
+                };
+                if map.is_closed_door(new_map_pos) {
+                    map.set_door(new_map_pos, true);
+                    opened_door = true;
+                } else if !map.blocked[new_idx] {
                     pos.0.x = new_pos.x;
                     pos.0.y = new_pos.y;
                     map.blocked[old_idx] = false;
@@ -64,8 +133,19 @@ pub(crate) fn monster_ai(
                     map.dangerous[old_idx] = false;
                     map.dangerous[new_idx] = true;
                     viewshed.dirty = true;
+                    entered_tile_queue.send(EnteredTileMessage {
+                        who: *entity,
+                        pos: new_map_pos,
+                    });
                 }
+            } else if alerted.is_some() {
+                // Can't get there, give up.
+                commands.remove_component::<Alerted>(*entity);
             }
         }
     }
+
+    if opened_door {
+        super::mark_viewsheds_dirty(world);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::components::{Item, Monster, Name, Position, Viewshed};
+use crate::components::{Gender, Item, Monster, Name, Position, Quantity, Viewshed};
 use crate::entity_adapter::EntityAdapterImpl;
 use crate::resources::{GameLog, OutputQueue};
 use crate::PlayerEntity;
@@ -7,10 +7,12 @@ use legion::world::SubWorld;
 
 #[system]
 #[read_component(Name)]
+#[read_component(Gender)]
 #[read_component(Item)]
 #[read_component(Position)]
 #[read_component(Viewshed)]
 #[read_component(Monster)]
+#[read_component(Quantity)]
 pub(crate) fn output(
     world: &mut SubWorld,
     #[resource] output: &mut OutputQueue,
@@ -0,0 +1,71 @@
+use crate::components::{EndTime, Particle, Position, Renderable};
+use crate::messages::ParticleMessage;
+use crate::queues::ParticleQueue;
+use crate::resources::Time;
+use bracket_lib::prelude::{BLACK, RGB, to_cp437};
+use legion::systems::CommandBuffer;
+use legion::*;
+
+/// How long a missile takes to fly over a tile.
+const MISSILE_FRAME_MS: i64 = 30;
+/// How long a blast takes to spread one tile further.
+const BLAST_FRAME_MS: i64 = 50;
+/// How long a tile of the blast burns after it is reached.
+const BLAST_BURN_MS: i64 = 150;
+const FLASH_MS: i64 = 150;
+
+/// Spawns the particles of the animations asked for. The missiles and
+/// blasts block the turns until they are done, the flashes don't.
+#[system]
+pub(crate) fn particles(
+    cb: &mut CommandBuffer,
+    #[resource] particle_queue: &ParticleQueue,
+    #[resource] time: &Time,
+) {
+    let now = time.real_time_ms;
+    let mut spawn = |pos, glyph, fg, start_time_ms, end_time_ms, blocking| {
+        cb.push((
+            Position(pos),
+            Renderable {
+                glyph,
+                fg,
+                bg: RGB::named(BLACK),
+                render_order: 0,
+            },
+            Particle {
+                start_time_ms,
+                blocking,
+            },
+            EndTime { end_time_ms },
+        ));
+    };
+
+    for message in particle_queue.try_iter() {
+        match message {
+            ParticleMessage::Missile { path, glyph, fg } => {
+                for (i, pos) in path.into_iter().enumerate() {
+                    let start = now + i as i64 * MISSILE_FRAME_MS;
+                    spawn(pos, glyph, fg, start, start + MISSILE_FRAME_MS, true);
+                }
+            }
+            ParticleMessage::Blast { center, tiles, fg } => {
+                for pos in tiles {
+                    let delta = pos - center;
+                    let distance = delta.x.abs().max(delta.y.abs()) as i64;
+                    let start = now + distance * BLAST_FRAME_MS;
+                    spawn(pos, to_cp437('░'), fg, start, start + BLAST_BURN_MS, true);
+                }
+            }
+            ParticleMessage::Flash { pos, fg } => {
+                spawn(pos, to_cp437('*'), fg, now, now + FLASH_MS, false);
+            }
+        }
+    }
+}
+
+/// Whether an animation that blocks the turns is still running.
+pub(crate) fn animating(world: &World, time: &Time) -> bool {
+    <(&Particle, &EndTime)>::query()
+        .iter(world)
+        .any(|(particle, end_time)| particle.blocking && end_time.end_time_ms > time.real_time_ms)
+}
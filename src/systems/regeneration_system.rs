@@ -0,0 +1,125 @@
+use super::TURN_TICKS;
+use crate::components::{
+    Attributes, CombatStats, Experience, HungerClock, HungerState, Monster, NaturalRegeneration,
+    Position, Viewshed,
+};
+use crate::messages::ReceiveHealthMessage;
+use crate::queues::ReceiveHealthQueue;
+use crate::resources::{PlayerEntity, PlayerPosition, Time};
+use legion::query::component;
+use legion::systems::CommandBuffer;
+use legion::world::SubWorld;
+use legion::*;
+use std::collections::HashSet;
+
+/// The turns it takes to regain a hit point at level 1 without bonuses.
+const BASE_TURNS: i32 = 20;
+/// However tough they get, it takes at least this many.
+const MIN_TURNS: i32 = 3;
+
+/// How many turns it takes to regain a hit point.
+fn regeneration_turns(level: i32, constitution: i32) -> i32 {
+    i32::max(MIN_TURNS, BASE_TURNS - (level - 1) - 2 * constitution)
+}
+
+/// Slowly heals the wounded while no enemy is in sight. The player's enemies
+/// are the monsters, and theirs is the player. Those weak from hunger don't
+/// heal at all.
+#[allow(clippy::too_many_arguments)]
+#[system]
+#[read_component(Attributes)]
+#[read_component(CombatStats)]
+#[read_component(Experience)]
+#[read_component(HungerClock)]
+#[read_component(Monster)]
+#[read_component(Position)]
+#[read_component(Viewshed)]
+#[write_component(NaturalRegeneration)]
+pub(crate) fn natural_regeneration(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] receive_health_queue: &ReceiveHealthQueue,
+    #[resource] player_entity: &PlayerEntity,
+    #[resource] player_position: &PlayerPosition,
+    #[resource] time: &Time,
+) {
+    let monster_positions: HashSet<_> = <&Position>::query()
+        .filter(component::<Monster>())
+        .iter(world)
+        .map(|pos| pos.0)
+        .collect();
+
+    let mut query = <(
+        Entity,
+        &CombatStats,
+        &Viewshed,
+        Option<&Experience>,
+        Option<&Attributes>,
+        Option<&HungerClock>,
+    )>::query();
+    let mut healers = vec![];
+    for (entity, stats, viewshed, experience, attributes, hunger) in query.iter(world) {
+        let level = experience.map_or(1, |experience| experience.level);
+        let constitution =
+            attributes.map_or(0, |attributes| Attributes::bonus(attributes.constitution));
+        let interval = regeneration_turns(level, constitution) as i64 * TURN_TICKS;
+        let in_danger = if *entity == player_entity.0 {
+            viewshed
+                .visible_tiles
+                .iter()
+                .any(|pos| monster_positions.contains(pos))
+        } else {
+            viewshed.visible_tiles.contains(&player_position.0)
+        };
+        let starving = hunger.is_some_and(|hunger| {
+            matches!(hunger.state, HungerState::Weak | HungerState::Fainting)
+        });
+        let can_heal = stats.hp < stats.max_hp && !in_danger && !starving;
+        healers.push((*entity, interval, can_heal));
+    }
+
+    for (entity, interval, can_heal) in healers {
+        let Ok(mut entry) = world.entry_mut(entity) else {
+            continue;
+        };
+        let Ok(regeneration) = entry.get_component_mut::<NaturalRegeneration>() else {
+            // Nobody is spawned with one, the clock starts the first time here.
+            cb.add_component(
+                entity,
+                NaturalRegeneration {
+                    next_tick: time.tick + interval,
+                },
+            );
+            continue;
+        };
+        if !can_heal {
+            regeneration.next_tick = time.tick + interval;
+            continue;
+        }
+        let mut amount = 0;
+        while regeneration.next_tick <= time.tick {
+            regeneration.next_tick += interval;
+            amount += 1;
+        }
+        if amount > 0 {
+            receive_health_queue.send(ReceiveHealthMessage {
+                target: entity,
+                amount,
+                raise_max_hp: false,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tougher_heal_faster() {
+        assert_eq!(regeneration_turns(1, 0), 20);
+        assert_eq!(regeneration_turns(5, 2), 12);
+        assert_eq!(regeneration_turns(1, -1), 22);
+        assert_eq!(regeneration_turns(30, 5), MIN_TURNS);
+    }
+}
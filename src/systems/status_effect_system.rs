@@ -0,0 +1,126 @@
+use crate::components::{CombatStats, StatusEffect, StatusEffects, StatusKind};
+use crate::messages::{InflictStatusMessage, ReceiveHealthMessage, SufferDamageMessage};
+use crate::queues::{InflictStatusQueue, ReceiveHealthQueue, SufferDamageQueue};
+use crate::resources::{OutputQueue, Time};
+use legion::systems::CommandBuffer;
+use legion::world::SubWorld;
+use legion::*;
+use std::collections::HashMap;
+
+/// A turn is a move, the effects that act every turn do it this often.
+pub(crate) const TURN_TICKS: i64 = 100;
+const POISON_DAMAGE: i32 = 2;
+const REGENERATION_HEAL: i32 = 2;
+
+/// Puts the new status effects on their targets, makes the effects act every
+/// turn, and removes those that have worn off.
+#[allow(clippy::too_many_arguments)]
+#[system]
+#[read_component(CombatStats)]
+#[write_component(StatusEffects)]
+pub(crate) fn status_effects(
+    world: &mut SubWorld,
+    cb: &mut CommandBuffer,
+    #[resource] inflict_status_queue: &InflictStatusQueue,
+    #[resource] suffer_damage_queue: &SufferDamageQueue,
+    #[resource] receive_health_queue: &ReceiveHealthQueue,
+    #[resource] output: &OutputQueue,
+    #[resource] time: &Time,
+) {
+    // Those without effects yet get them through the command buffer.
+    let mut added: HashMap<Entity, StatusEffects> = HashMap::new();
+    for InflictStatusMessage {
+        target,
+        kind,
+        turns,
+    } in inflict_status_queue.try_iter()
+    {
+        let Ok(mut entry) = world.entry_mut(target) else {
+            continue;
+        };
+        let effects = match entry.get_component_mut::<StatusEffects>() {
+            Ok(effects) => effects,
+            Err(_) => added
+                .entry(target)
+                .or_insert(StatusEffects { effects: vec![] }),
+        };
+        let end_tick = time.tick + turns as i64 * TURN_TICKS;
+        match effects
+            .effects
+            .iter_mut()
+            .find(|effect| effect.kind == kind)
+        {
+            Some(effect) => effect.end_tick = effect.end_tick.max(end_tick),
+            None => effects.effects.push(StatusEffect {
+                kind,
+                end_tick,
+                next_tick: time.tick + TURN_TICKS,
+            }),
+        }
+        output.the(target).is(target).s(kind.adjective());
+    }
+    for (target, effects) in added {
+        cb.add_component(target, effects);
+    }
+
+    for (entity, effects, stats) in
+        <(Entity, &mut StatusEffects, Option<&CombatStats>)>::query().iter_mut(world)
+    {
+        for effect in effects.effects.iter_mut() {
+            while effect.next_tick <= time.tick.min(effect.end_tick) {
+                effect.next_tick += TURN_TICKS;
+                match effect.kind {
+                    StatusKind::Poison => suffer_damage_queue.send(SufferDamageMessage {
+                        target: *entity,
+                        amount: POISON_DAMAGE,
+                        attacker: None,
+                    }),
+                    StatusKind::Regeneration if stats.is_some_and(|s| s.hp < s.max_hp) => {
+                        receive_health_queue.send(ReceiveHealthMessage {
+                            target: *entity,
+                            amount: REGENERATION_HEAL,
+                            raise_max_hp: false,
+                        })
+                    }
+                    _ => (),
+                }
+            }
+        }
+        effects.effects.retain(|effect| {
+            let active = effect.end_tick > time.tick;
+            if !active {
+                output
+                    .the(*entity)
+                    .is(*entity)
+                    .s("no longer")
+                    .s(effect.kind.adjective());
+            }
+            active
+        });
+        if effects.effects.is_empty() {
+            cb.remove_component::<StatusEffects>(*entity);
+        }
+    }
+}
+
+/// Whether the confusion makes them stumble in a random direction this time.
+pub(crate) fn stumbles(
+    effects: Option<&StatusEffects>,
+    rng: &mut bracket_lib::prelude::RandomNumberGenerator,
+) -> bool {
+    effects.is_some_and(|effects| effects.has(StatusKind::Confusion)) && rng.roll_dice(1, 2) == 1
+}
+
+/// How fast they regain energy, in percent of the normal speed.
+pub(crate) fn speed(effects: Option<&StatusEffects>) -> i32 {
+    let mut speed = 100;
+    if let Some(effects) = effects {
+        if effects.has(StatusKind::Haste) {
+            speed *= 2;
+        }
+        if effects.has(StatusKind::Slow) {
+            speed /= 2;
+        }
+    }
+    speed
+}
@@ -0,0 +1,241 @@
+use super::TURN_TICKS;
+use crate::components::{
+    Alerted, BlocksTile, Hidden, Monster, PassivePerception, Position, StatusKind, Trap,
+    TrapEffect, Viewshed,
+};
+use crate::messages::{
+    EnteredTileMessage, InflictStatusMessage, SufferDamageMessage, WantsToSearchMessage,
+};
+use crate::positions::MapPosition;
+use crate::queues::{EnteredTileQueue, InflictStatusQueue, SufferDamageQueue, WantsToSearchQueue};
+use crate::resources::{
+    Map, OutputQueue, PlayerEntity, PlayerPosition, PlayerTarget, TileType, Time,
+};
+use ::bracket_lib::prelude::{Algorithm2D, DistanceAlg, RandomNumberGenerator};
+use ::legion::query::component;
+use ::legion::systems::CommandBuffer;
+use ::legion::world::SubWorld;
+use ::legion::*;
+
+/// How far away hidden traps are noticed without searching.
+const PASSIVE_RANGE: f32 = 2.0;
+/// What a 1d20 roll must reach to notice a trap without searching.
+const PASSIVE_DIFFICULTY: i32 = 18;
+/// The same when searching.
+const SEARCH_RANGE: f32 = 3.0;
+const SEARCH_DIFFICULTY: i32 = 8;
+
+/// Fires the traps that someone stepped on.
+#[allow(clippy::too_many_arguments)]
+#[system]
+#[read_component(Trap)]
+#[read_component(Monster)]
+#[read_component(BlocksTile)]
+#[write_component(Position)]
+#[write_component(Viewshed)]
+pub(crate) fn trap(
+    world: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] entered_tile_queue: &EnteredTileQueue,
+    #[resource] suffer_damage_queue: &SufferDamageQueue,
+    #[resource] inflict_status_queue: &InflictStatusQueue,
+    #[resource] output: &OutputQueue,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] map: &mut Map,
+    #[resource] player_entity: &PlayerEntity,
+    #[resource] player_pos: &mut PlayerPosition,
+    #[resource] player_target: &mut PlayerTarget,
+) {
+    for EnteredTileMessage { who, pos } in entered_tile_queue.try_iter() {
+        let traps: Vec<(Entity, TrapEffect)> = <(Entity, &Trap, &Position)>::query()
+            .iter(world)
+            .filter(|(_, _, trap_pos)| trap_pos.0 == pos)
+            .map(|(entity, trap, _)| (*entity, trap.effect))
+            .collect();
+
+        // Only traps fired where the player sees them are revealed.
+        let seen = who == player_entity.0
+            || world.entry_ref(player_entity.0).is_ok_and(|entry| {
+                entry
+                    .get_component::<Viewshed>()
+                    .is_ok_and(|viewshed| viewshed.visible_tiles.contains(&pos))
+            });
+        for (trap_entity, effect) in traps {
+            if seen {
+                commands.remove_component::<Hidden>(trap_entity);
+                let idx = map.map_pos_to_idx(pos);
+                map.known_traps[idx] = true;
+            }
+            if who == player_entity.0 {
+                *player_target = PlayerTarget::None;
+            }
+
+            output.the(who).v(who, "step").s("on").the(trap_entity);
+            match effect {
+                TrapEffect::Damage(amount) => {
+                    output
+                        .the(trap_entity)
+                        .v(trap_entity, "hurt")
+                        .the(who)
+                        .string(format!(", for {amount} hp"));
+                    suffer_damage_queue.send(SufferDamageMessage {
+                        target: who,
+                        amount,
+                        attacker: None,
+                    });
+                }
+                TrapEffect::Teleport => {
+                    let dest = (0..1000)
+                        .map(|_| rng.range(0, map.tiles.len() as i32) as usize)
+                        .find(|idx| map.tiles[*idx] == TileType::Floor && !map.blocked[*idx]);
+                    let Some(dest) = dest else {
+                        continue;
+                    };
+                    let dest = map.index_to_point2d(dest);
+                    let dest = MapPosition {
+                        x: dest.x,
+                        y: dest.y,
+                    };
+                    if let Ok(mut entry) = world.entry_mut(who) {
+                        // Move it in the map's index too, others may move this tick.
+                        if entry.get_component::<BlocksTile>().is_ok() {
+                            let monster = entry.get_component::<Monster>().is_ok();
+                            let old_idx = map.map_pos_to_idx(pos);
+                            let new_idx = map.map_pos_to_idx(dest);
+                            map.blocked[old_idx] = false;
+                            map.blocked[new_idx] = true;
+                            map.dangerous[old_idx] = false;
+                            map.dangerous[new_idx] = monster;
+                            map.tile_content[old_idx].retain(|entity| *entity != who);
+                            map.tile_content[new_idx].push(who);
+                        }
+                        if let Ok(position) = entry.get_component_mut::<Position>() {
+                            position.0 = dest;
+                        }
+                        if let Ok(viewshed) = entry.get_component_mut::<Viewshed>() {
+                            viewshed.dirty = true;
+                        }
+                    }
+                    if who == player_entity.0 {
+                        player_pos.0 = dest;
+                    }
+                    output
+                        .the(trap_entity)
+                        .v(trap_entity, "whisk")
+                        .the(who)
+                        .s("away");
+                }
+                TrapEffect::Alarm(radius) => {
+                    output.the(trap_entity).v(trap_entity, "ring").s("loudly");
+                    for (monster, monster_pos) in <(Entity, &Position)>::query()
+                        .filter(component::<Monster>())
+                        .iter(world)
+                    {
+                        let distance =
+                            DistanceAlg::Pythagoras.distance2d(monster_pos.0.into(), pos.into());
+                        if distance <= radius as f32 {
+                            commands.add_component(*monster, Alerted { pos });
+                        }
+                    }
+                }
+                TrapEffect::Poison(turns) => {
+                    output.the(trap_entity).v(trap_entity, "prick").the(who);
+                    inflict_status_queue.send(InflictStatusMessage {
+                        target: who,
+                        kind: StatusKind::Poison,
+                        turns,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Notices the hidden traps around the player, searching finds them further
+/// away and more easily.
+#[allow(clippy::too_many_arguments)]
+#[system]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+#[read_component(Position)]
+#[read_component(Viewshed)]
+#[write_component(PassivePerception)]
+pub(crate) fn perception(
+    world: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] wants_to_search_queue: &WantsToSearchQueue,
+    #[resource] output: &OutputQueue,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] map: &mut Map,
+    #[resource] player_entity: &PlayerEntity,
+    #[resource] player_target: &mut PlayerTarget,
+    #[resource] time: &Time,
+) {
+    // This runs whenever anyone gets to act, but the player only notices
+    // traps without searching once a turn.
+    let mut passive = true;
+    if let Ok(mut entry) = world.entry_mut(player_entity.0) {
+        match entry.get_component_mut::<PassivePerception>() {
+            Ok(perception) if perception.next_tick > time.tick => passive = false,
+            Ok(perception) => perception.next_tick = time.tick + TURN_TICKS,
+            Err(_) => commands.add_component(
+                player_entity.0,
+                PassivePerception {
+                    next_tick: time.tick + TURN_TICKS,
+                },
+            ),
+        }
+    }
+
+    let mut searchers: Vec<(Entity, bool)> = wants_to_search_queue
+        .try_iter()
+        .map(|WantsToSearchMessage { who }| (who, true))
+        .collect();
+    if passive && !searchers.iter().any(|(who, _)| *who == player_entity.0) {
+        searchers.push((player_entity.0, false));
+    }
+
+    for (who, searching) in searchers {
+        let Ok(entry) = world.entry_ref(who) else {
+            continue;
+        };
+        let (Ok(pos), Ok(viewshed)) = (
+            entry.get_component::<Position>(),
+            entry.get_component::<Viewshed>(),
+        ) else {
+            continue;
+        };
+        let (range, difficulty) = if searching {
+            (SEARCH_RANGE, SEARCH_DIFFICULTY)
+        } else {
+            (PASSIVE_RANGE, PASSIVE_DIFFICULTY)
+        };
+
+        let mut found = false;
+        for (trap_entity, trap_pos) in <(Entity, &Position)>::query()
+            .filter(component::<Trap>() & component::<Hidden>())
+            .iter(world)
+        {
+            let idx = map.map_pos_to_idx(trap_pos.0);
+            let distance = DistanceAlg::Pythagoras.distance2d(pos.0.into(), trap_pos.0.into());
+            if map.known_traps[idx]
+                || distance > range
+                || !viewshed.visible_tiles.contains(&trap_pos.0)
+            {
+                continue;
+            }
+            if rng.roll_dice(1, 20) >= difficulty {
+                commands.remove_component::<Hidden>(*trap_entity);
+                map.known_traps[idx] = true;
+                output.the(who).v(who, "spot").a(*trap_entity);
+                found = true;
+            }
+        }
+
+        if found && who == player_entity.0 {
+            *player_target = PlayerTarget::None;
+        } else if searching && !found {
+            output.the(who).v(who, "find").s("nothing");
+        }
+    }
+}
@@ -7,6 +7,13 @@ use ::legion::*;
 
 struct ViewshedPlayerUpdate(bool);
 
+/// Makes everyone look again, ie after a door opened or closed.
+pub(crate) fn mark_viewsheds_dirty<W: EntityStore>(world: &mut W) {
+    for viewshed in <&mut Viewshed>::query().iter_mut(world) {
+        viewshed.dirty = true;
+    }
+}
+
 pub(crate) fn add_viewshed_system(
     ecs: &mut crate::ecs::Ecs,
     schedule_builder: &mut systems::Builder,